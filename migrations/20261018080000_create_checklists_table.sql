@@ -0,0 +1,11 @@
+-- Create checklists table
+CREATE TABLE IF NOT EXISTS checklists (
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    owner_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    tasks jsonb NOT NULL,
+    created_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS checklists_owner_id_idx ON checklists (owner_id);
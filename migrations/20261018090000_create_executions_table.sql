@@ -0,0 +1,13 @@
+-- Create executions table
+CREATE TABLE IF NOT EXISTS executions (
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    checklist_id uuid NOT NULL REFERENCES checklists (id),
+    owner_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    task_statuses jsonb NOT NULL,
+    state TEXT NOT NULL,
+    created_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS executions_owner_id_idx ON executions (owner_id);
@@ -0,0 +1,2 @@
+-- Store the reason given when an execution is skipped
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS reason TEXT;
@@ -0,0 +1,37 @@
+-- Give every existing checklist task a stable identifier
+UPDATE checklists
+SET tasks = (
+    SELECT COALESCE(
+        jsonb_agg(
+            CASE WHEN task ? 'id' THEN task
+            ELSE task || jsonb_build_object('id', gen_random_uuid())
+            END
+            ORDER BY position
+        ),
+        '[]'::jsonb
+    )
+    FROM jsonb_array_elements(tasks) WITH ORDINALITY AS t (task, position)
+);
+
+-- Match task statuses of existing executions with checklist tasks by position and title,
+-- falling back to a fresh identifier when the checklist has changed since
+UPDATE executions e
+SET task_statuses = (
+    SELECT COALESCE(
+        jsonb_agg(
+            CASE WHEN status ? 'id' THEN status
+            ELSE status || jsonb_build_object('id', COALESCE(
+                CASE WHEN c.tasks -> (position::int - 1) ->> 'title' = status ->> 'title'
+                THEN c.tasks -> (position::int - 1) -> 'id'
+                END,
+                to_jsonb(gen_random_uuid())
+            ))
+            END
+            ORDER BY position
+        ),
+        '[]'::jsonb
+    )
+    FROM jsonb_array_elements(e.task_statuses) WITH ORDINALITY AS s (status, position)
+)
+FROM checklists c
+WHERE c.id = e.checklist_id;
@@ -0,0 +1,25 @@
+-- Keep every edit of a checklist as an immutable version
+CREATE TABLE IF NOT EXISTS checklist_versions (
+    checklist_id uuid NOT NULL REFERENCES checklists (id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    PRIMARY KEY (checklist_id, version),
+    title TEXT NOT NULL,
+    tasks jsonb NOT NULL,
+    created_by uuid REFERENCES users (id) ON DELETE SET NULL,
+    created_at timestamptz NOT NULL
+);
+
+INSERT INTO checklist_versions (checklist_id, version, title, tasks, created_by, created_at)
+SELECT id, 1, title, tasks, owner_id, created_at FROM checklists;
+
+ALTER TABLE checklists ADD COLUMN current_version INT NOT NULL DEFAULT 1;
+ALTER TABLE checklists ALTER COLUMN current_version DROP DEFAULT;
+ALTER TABLE checklists DROP COLUMN title;
+ALTER TABLE checklists DROP COLUMN tasks;
+
+-- Pin every execution to the version of the checklist it was started from
+ALTER TABLE executions ADD COLUMN checklist_version INT NOT NULL DEFAULT 1;
+ALTER TABLE executions ALTER COLUMN checklist_version DROP DEFAULT;
+ALTER TABLE executions
+    ADD FOREIGN KEY (checklist_id, checklist_version)
+    REFERENCES checklist_versions (checklist_id, version);
@@ -0,0 +1,3 @@
+-- Store template variables and the values executions were started with
+ALTER TABLE checklist_versions ADD COLUMN variables jsonb NOT NULL DEFAULT '[]';
+ALTER TABLE executions ADD COLUMN variables jsonb NOT NULL DEFAULT '{}';
@@ -0,0 +1,16 @@
+-- Create batches table grouping executions of one checklist against many targets
+CREATE TABLE IF NOT EXISTS batches (
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    checklist_id uuid NOT NULL REFERENCES checklists (id),
+    owner_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    created_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS batches_owner_id_idx ON batches (owner_id);
+
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS batch_id uuid REFERENCES batches (id) ON DELETE CASCADE;
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS target TEXT;
+
+CREATE INDEX IF NOT EXISTS executions_batch_id_idx ON executions (batch_id);
@@ -0,0 +1,36 @@
+-- Create teams, their members and access of users to checklists and executions
+CREATE TABLE IF NOT EXISTS teams (
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    name TEXT NOT NULL,
+    created_at timestamptz NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS team_members (
+    team_id uuid NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (team_id, user_id),
+    role TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS team_members_user_id_idx ON team_members (user_id);
+
+ALTER TABLE checklists ADD COLUMN IF NOT EXISTS team_id uuid REFERENCES teams (id);
+ALTER TABLE executions ADD COLUMN IF NOT EXISTS team_id uuid REFERENCES teams (id);
+ALTER TABLE batches ADD COLUMN IF NOT EXISTS team_id uuid REFERENCES teams (id);
+
+-- Users that created a checklist or an execution are its owners, members of its team get
+-- their role in the team.
+CREATE VIEW checklist_access AS
+    SELECT id AS checklist_id, owner_id AS user_id, 'owner' AS role FROM checklists
+    UNION ALL
+    SELECT c.id, m.user_id, m.role
+    FROM checklists c
+    JOIN team_members m ON m.team_id = c.team_id;
+
+CREATE VIEW execution_access AS
+    SELECT id AS execution_id, owner_id AS user_id, 'owner' AS role FROM executions
+    UNION ALL
+    SELECT e.id, m.user_id, m.role
+    FROM executions e
+    JOIN team_members m ON m.team_id = e.team_id;
@@ -0,0 +1,25 @@
+-- Create grants of access to single checklists for other users
+CREATE TABLE IF NOT EXISTS checklist_grants (
+    checklist_id uuid NOT NULL REFERENCES checklists (id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (checklist_id, user_id),
+    access TEXT NOT NULL,
+    created_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS checklist_grants_user_id_idx ON checklist_grants (user_id);
+
+-- Users with a grant get the role matching its access.
+CREATE OR REPLACE VIEW checklist_access AS
+    SELECT id AS checklist_id, owner_id AS user_id, 'owner' AS role FROM checklists
+    UNION ALL
+    SELECT c.id, m.user_id, m.role
+    FROM checklists c
+    JOIN team_members m ON m.team_id = c.team_id
+    UNION ALL
+    SELECT checklist_id, user_id, CASE access
+        WHEN 'edit' THEN 'editor'
+        WHEN 'execute' THEN 'executor'
+        ELSE 'viewer'
+    END
+    FROM checklist_grants;
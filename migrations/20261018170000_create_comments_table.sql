@@ -0,0 +1,13 @@
+-- Create comments on executions and their tasks
+CREATE TABLE IF NOT EXISTS comments (
+    id uuid NOT NULL PRIMARY KEY,
+    execution_id uuid NOT NULL REFERENCES executions (id) ON DELETE CASCADE,
+    -- Comments without a task are about the whole execution.
+    task_id uuid,
+    author_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    body TEXT NOT NULL,
+    created_at timestamptz NOT NULL,
+    updated_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS comments_execution_id_idx ON comments (execution_id, created_at);
@@ -0,0 +1,12 @@
+-- Create links sharing executions with people without an account
+CREATE TABLE IF NOT EXISTS share_links (
+    id uuid NOT NULL PRIMARY KEY,
+    execution_id uuid NOT NULL REFERENCES executions (id) ON DELETE CASCADE,
+    -- Only a hash of the token is stored, so that links cannot be recovered from the database.
+    token_hash BYTEA NOT NULL UNIQUE,
+    created_by uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL,
+    expires_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS share_links_execution_id_idx ON share_links (execution_id);
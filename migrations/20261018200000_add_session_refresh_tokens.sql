@@ -0,0 +1,7 @@
+-- Sessions slide until an absolute expiry, and can be renewed with a refresh token
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS expires_at timestamptz;
+UPDATE sessions SET expires_at = valid_until WHERE expires_at IS NULL;
+ALTER TABLE sessions ALTER COLUMN expires_at SET NOT NULL;
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS refresh_token bytea UNIQUE;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS refresh_valid_until timestamptz;
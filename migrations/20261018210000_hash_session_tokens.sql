@@ -0,0 +1,3 @@
+-- Session and refresh tokens are stored hashed from now on. Existing sessions hold raw tokens,
+-- which would never match a hash, so everyone has to log in again.
+DELETE FROM sessions;
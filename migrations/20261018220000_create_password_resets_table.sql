@@ -0,0 +1,9 @@
+-- Create single-use tokens for resetting forgotten passwords
+CREATE TABLE IF NOT EXISTS password_resets (
+    token_hash bytea PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL,
+    expires_at timestamptz NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS password_resets_user_id_idx ON password_resets (user_id);
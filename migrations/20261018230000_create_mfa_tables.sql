@@ -0,0 +1,24 @@
+-- Add TOTP authenticators, recovery codes and pending second-factor login challenges
+CREATE TABLE IF NOT EXISTS totp_authenticators (
+    user_id uuid PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    secret bytea NOT NULL,
+    created_at timestamptz NOT NULL,
+    -- Authenticators are only required at login once confirmed with a code
+    confirmed_at timestamptz,
+    -- Time step of the last accepted code, so that codes cannot be replayed
+    last_used_step bigint
+);
+
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash bytea NOT NULL,
+    PRIMARY KEY (user_id, code_hash)
+);
+
+CREATE TABLE IF NOT EXISTS mfa_challenges (
+    token_hash bytea PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL,
+    expires_at timestamptz NOT NULL,
+    failed_attempts integer NOT NULL DEFAULT 0
+);
@@ -0,0 +1,7 @@
+-- Count failed second-factor attempts per user, so that codes cannot be guessed by starting new logins
+ALTER TABLE totp_authenticators
+    ADD COLUMN IF NOT EXISTS failed_attempts integer NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS locked_until timestamptz;
+
+COMMENT ON COLUMN totp_authenticators.secret IS
+    'Stored unencrypted: the server needs the secret itself to compute codes, and no key management is in place to encrypt it with. Protect database access and backups accordingly.';
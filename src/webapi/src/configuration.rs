@@ -4,6 +4,10 @@ use secrecy::{ExposeSecret, Secret};
 pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
+    #[serde(default)]
+    pub session: SessionSettings,
+    #[serde(default)]
+    pub notifier: NotifierSettings,
 }
 
 #[derive(serde::Deserialize)]
@@ -21,6 +25,58 @@ pub struct ApplicationSettings {
     pub host: String,
 }
 
+/// How notifications, like password reset tokens, are delivered to users.
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotifierSettings {
+    /// Writes notifications to the application log.
+    #[default]
+    Log,
+    /// Appends notifications to a file.
+    File { path: String },
+}
+
+/// Lifetimes of sessions of logged in users.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct SessionSettings {
+    /// Session tokens expire when not used for this long.
+    pub idle_timeout_minutes: i64,
+    /// Session tokens expire this long after they were issued, however often they are used.
+    pub absolute_lifetime_hours: i64,
+    /// Refresh tokens can be exchanged for new session tokens for this long after logging in.
+    pub refresh_lifetime_days: i64,
+    /// Record client IPs of sessions from `Forwarded`/`X-Forwarded-For` headers instead of the
+    /// connection's peer address. Clients can set these headers to anything, so this should only
+    /// be enabled behind a reverse proxy that overwrites them.
+    #[serde(default)]
+    pub trust_forwarded_headers: bool,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            idle_timeout_minutes: 12 * 60,
+            absolute_lifetime_hours: 7 * 24,
+            refresh_lifetime_days: 30,
+            trust_forwarded_headers: false,
+        }
+    }
+}
+
+impl SessionSettings {
+    pub fn idle_timeout(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.idle_timeout_minutes)
+    }
+
+    pub fn absolute_lifetime(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.absolute_lifetime_hours)
+    }
+
+    pub fn refresh_lifetime(&self) -> chrono::Duration {
+        chrono::Duration::days(self.refresh_lifetime_days)
+    }
+}
+
 impl DatabaseSettings {
     pub fn connection_string(&self) -> Secret<String> {
         Secret::new(format!(
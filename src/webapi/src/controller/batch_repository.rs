@@ -0,0 +1,134 @@
+use crate::{
+    controller::execution_repository::{self, ExecutionRepositoryError},
+    models::{batch::Batch, checklist::VariableValue},
+};
+use chrono::Utc;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+/// Largest number of targets a batch can be started for.
+const MAX_TARGETS: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BatchRepositoryError {
+    #[error("batch not found")]
+    BatchNotFound,
+    #[error("invalid targets: {reason}")]
+    InvalidTargets { reason: String },
+    #[error(transparent)]
+    Execution(#[from] ExecutionRepositoryError),
+    #[error("internal error")]
+    InternalError,
+}
+
+fn validate_targets(targets: &[String]) -> Result<(), BatchRepositoryError> {
+    let reason = if targets.is_empty() {
+        "at least one target is required".to_owned()
+    } else if targets.len() > MAX_TARGETS {
+        format!("at most {MAX_TARGETS} targets are allowed")
+    } else if targets.iter().any(|t| t.trim().is_empty()) {
+        "target cannot be empty".to_owned()
+    } else {
+        let mut seen = HashSet::new();
+        match targets.iter().find(|t| !seen.insert(t.as_str())) {
+            Some(target) => format!("duplicate target '{target}'"),
+            None => return Ok(()),
+        }
+    };
+    Err(BatchRepositoryError::InvalidTargets { reason })
+}
+
+/// Starts an execution of a checklist for every target, all in one batch.
+#[tracing::instrument(name = "Starting a batch of executions", skip(pool, targets))]
+pub async fn start_batch(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    targets: &[String],
+    variables: &BTreeMap<String, VariableValue>,
+) -> Result<Uuid, BatchRepositoryError> {
+    validate_targets(targets)?;
+    let execution =
+        execution_repository::prepare_execution(pool, user_id, checklist_id, variables).await?;
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        BatchRepositoryError::InternalError
+    })?;
+
+    let batch_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+    INSERT INTO batches (id, checklist_id, owner_id, team_id, title, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        batch_id,
+        checklist_id,
+        user_id,
+        execution.team_id(),
+        execution.title(),
+        Utc::now()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create batch in database: {:?}", e);
+        BatchRepositoryError::InternalError
+    })?;
+    for target in targets.iter() {
+        execution_repository::insert_execution(
+            &mut *transaction,
+            user_id,
+            &execution,
+            Some(batch_id),
+            Some(target),
+        )
+        .await?;
+    }
+
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        BatchRepositoryError::InternalError
+    })?;
+
+    Ok(batch_id)
+}
+
+#[tracing::instrument(name = "Fetching a batch", skip(pool))]
+pub async fn get_batch(
+    pool: &PgPool,
+    user_id: Uuid,
+    batch_id: Uuid,
+) -> Result<Batch, BatchRepositoryError> {
+    let batch = sqlx::query!(
+        r#"
+    SELECT b.id, b.checklist_id, b.title, b.created_at
+    FROM batches b
+    WHERE b.id = $1 AND (
+        b.owner_id = $2
+        OR EXISTS (SELECT 1 FROM team_members m WHERE m.team_id = b.team_id AND m.user_id = $2)
+    )
+        "#,
+        batch_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => BatchRepositoryError::BatchNotFound,
+        e => {
+            tracing::error!("Failed to fetch batch from database: {:?}", e);
+            BatchRepositoryError::InternalError
+        }
+    })?;
+    let executions = execution_repository::list_batch_executions(pool, batch.id).await?;
+
+    Ok(Batch::new(
+        batch.id,
+        batch.checklist_id,
+        batch.title,
+        batch.created_at,
+        &executions,
+    ))
+}
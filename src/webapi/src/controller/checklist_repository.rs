@@ -0,0 +1,687 @@
+use crate::{
+    models::{
+        checklist::{
+            placeholders, Checklist, ChecklistSummary, ChecklistVersionSummary, Task, TaskInput,
+            Variable,
+        },
+        team::Role,
+    },
+    routes::checklist::ChecklistRequest,
+};
+use chrono::Utc;
+use sqlx::{types::Json, PgPool, Postgres, Transaction};
+use std::{collections::HashMap, str::FromStr};
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChecklistRepositoryError {
+    #[error("checklist not found")]
+    ChecklistNotFound,
+    #[error("invalid checklist: {reason}")]
+    InvalidChecklist { reason: String },
+    #[error("internal error")]
+    InternalError,
+}
+
+/// Validates tasks of a checklist in order, keeping track of the tasks seen so far.
+#[derive(Default)]
+struct TaskValidator<'a> {
+    /// Declared variables by their names, and whether they are lists.
+    variables: HashMap<&'a str, bool>,
+    /// Inputs of tasks by their ids.
+    inputs: HashMap<Uuid, Option<&'a TaskInput>>,
+    /// Innermost repeated section each task is part of.
+    repeated_in: HashMap<Uuid, Option<Uuid>>,
+    /// Tasks containing the ones being validated.
+    ancestors: Vec<&'a Task>,
+}
+
+impl<'a> TaskValidator<'a> {
+    fn new(variables: &'a [Variable]) -> Result<Self, String> {
+        let mut validator = Self::default();
+        for variable in variables.iter() {
+            if variable.name.is_empty()
+                || !variable
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(format!(
+                    "variable name '{}' can only contain letters, digits and underscores",
+                    variable.name
+                ));
+            }
+            if validator
+                .variables
+                .insert(&variable.name, variable.list)
+                .is_some()
+            {
+                return Err(format!("duplicate variable {}", variable.name));
+            }
+        }
+        Ok(validator)
+    }
+
+    /// Checks that placeholders refer to declared variables; lists can only be used within
+    /// sections repeated over them.
+    fn validate_placeholders(&self, text: &str) -> Result<(), String> {
+        for name in placeholders(text) {
+            match self.variables.get(name) {
+                None => return Err(format!("placeholder '{name}' is not a declared variable")),
+                Some(true)
+                    if !self
+                        .ancestors
+                        .iter()
+                        .any(|t| t.repeat_for.as_deref() == Some(name)) =>
+                {
+                    return Err(format!(
+                        "list '{name}' can only be used in sections repeated over it"
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `task` can refer to the task with the given id, i.e. that it is an earlier
+    /// task, and one outside of repeated sections unless `task` is part of the same section.
+    fn validate_reference(&self, task: &Task, id: &Uuid) -> Result<(), String> {
+        match self.repeated_in.get(id) {
+            None => Err(format!(
+                "task '{}' can only refer to earlier tasks",
+                task.title
+            )),
+            Some(Some(section)) if !self.ancestors.iter().any(|t| t.id == *section) => {
+                Err(format!(
+                    "task '{}' cannot refer to tasks of a repeated section it is not part of",
+                    task.title
+                ))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn validate_tasks(&mut self, tasks: &'a [Task]) -> Result<(), String> {
+        for task in tasks {
+            self.validate_task(task)?;
+        }
+        Ok(())
+    }
+
+    fn validate_task(&mut self, task: &'a Task) -> Result<(), String> {
+        if task.title.trim().is_empty() {
+            return Err("task title cannot be empty".to_owned());
+        }
+        self.validate_placeholders(&task.title)?;
+
+        // Conditions may only refer to earlier tasks, which rules out cycles.
+        for condition in [&task.visible_if, &task.required_if].into_iter().flatten() {
+            self.validate_reference(task, &condition.task_id)?;
+            match self.inputs[&condition.task_id] {
+                Some(input) => condition
+                    .validate(input)
+                    .map_err(|e| format!("invalid condition of task '{}': {e}", task.title))?,
+                None => {
+                    return Err(format!(
+                        "condition of task '{}' refers to a task without input",
+                        task.title
+                    ))
+                }
+            }
+        }
+        // Same for dependencies; a task can also never be completed before its own section.
+        for dependency in task.depends_on.iter() {
+            self.validate_reference(task, dependency)?;
+            if self.ancestors.iter().any(|t| t.id == *dependency) {
+                return Err(format!(
+                    "task '{}' cannot depend on its own section",
+                    task.title
+                ));
+            }
+        }
+
+        let has_children = !task.children.is_empty() || task.include.is_some();
+        if let Some(include) = &task.include {
+            if !task.children.is_empty() || task.input.is_some() {
+                return Err(format!(
+                    "task '{}' including a checklist cannot have sub-tasks or input",
+                    task.title
+                ));
+            }
+            if include.version.is_some() {
+                return Err(format!(
+                    "task '{}' can only include the current version of a checklist",
+                    task.title
+                ));
+            }
+        }
+        if task.requires_verification && has_children {
+            return Err(format!(
+                "task '{}' with sub-tasks cannot require verification",
+                task.title
+            ));
+        }
+        if task.requires_signature && has_children {
+            return Err(format!(
+                "task '{}' with sub-tasks cannot require a signature",
+                task.title
+            ));
+        }
+        if task.sequential && !has_children {
+            return Err(format!(
+                "task '{}' without sub-tasks cannot be sequential",
+                task.title
+            ));
+        }
+        if let Some(list) = &task.repeat_for {
+            if self.variables.get(list.as_str()) != Some(&true) {
+                return Err(format!(
+                    "task '{}' can only be repeated over a declared list",
+                    task.title
+                ));
+            }
+            if !has_children {
+                return Err(format!(
+                    "task '{}' without sub-tasks cannot be repeated",
+                    task.title
+                ));
+            }
+        }
+        if let Some(input) = &task.input {
+            if has_children {
+                return Err(format!(
+                    "task '{}' with sub-tasks cannot ask for input",
+                    task.title
+                ));
+            }
+            input
+                .validate_definition()
+                .map_err(|e| format!("invalid input of task '{}': {e}", task.title))?;
+        }
+
+        if self.inputs.insert(task.id, task.input.as_ref()).is_some() {
+            return Err(format!("duplicate task id {}", task.id));
+        }
+        let repeated_in = self
+            .ancestors
+            .iter()
+            .rev()
+            .find(|t| t.repeat_for.is_some())
+            .map(|t| t.id);
+        self.repeated_in.insert(task.id, repeated_in);
+
+        self.ancestors.push(task);
+        self.validate_tasks(&task.children)?;
+        self.ancestors.pop();
+        Ok(())
+    }
+}
+
+fn validate_checklist(checklist: &ChecklistRequest) -> Result<(), ChecklistRepositoryError> {
+    if checklist.title.trim().is_empty() {
+        return Err(ChecklistRepositoryError::InvalidChecklist {
+            reason: "title cannot be empty".to_owned(),
+        });
+    }
+    TaskValidator::new(&checklist.variables)
+        .and_then(|mut validator| {
+            validator.validate_placeholders(&checklist.title)?;
+            validator.validate_tasks(&checklist.tasks)
+        })
+        .map_err(|reason| ChecklistRepositoryError::InvalidChecklist { reason })
+}
+
+struct ChecklistRow {
+    id: Uuid,
+    version: i32,
+    title: String,
+    variables: Json<Vec<Variable>>,
+    tasks: Json<Vec<Task>>,
+    owner: String,
+    team_id: Option<Uuid>,
+}
+
+impl From<ChecklistRow> for Checklist {
+    fn from(row: ChecklistRow) -> Self {
+        Checklist {
+            id: row.id,
+            version: row.version,
+            title: row.title,
+            variables: row.variables.0,
+            tasks: row.tasks.0,
+            owner: row.owner,
+            team_id: row.team_id,
+        }
+    }
+}
+
+fn included_checklist_not_found(
+    id: Uuid,
+) -> impl Fn(ChecklistRepositoryError) -> ChecklistRepositoryError {
+    move |e| match e {
+        ChecklistRepositoryError::ChecklistNotFound => ChecklistRepositoryError::InvalidChecklist {
+            reason: format!("included checklist {id} not found"),
+        },
+        e => e,
+    }
+}
+
+/// Checks that the user saving `tasks` has access to the checklists they include directly.
+///
+/// Checklists included through those were checked when the including checklist was saved, so
+/// they are not checked again, neither here nor when the checklist is executed.
+async fn check_included_checklists_access(
+    pool: &PgPool,
+    user_id: Uuid,
+    tasks: &[Task],
+) -> Result<(), ChecklistRepositoryError> {
+    for id in Task::included_checklists(tasks) {
+        get_checklist(pool, user_id, id)
+            .await
+            .map_err(included_checklist_not_found(id))?;
+    }
+    Ok(())
+}
+
+/// Fetches current versions of all checklists included by `tasks`, directly or indirectly,
+/// regardless of the access of the current user.
+///
+/// Fails if any of them is missing or includes the checklist with id `checklist_id`.
+async fn fetch_included_checklists(
+    pool: &PgPool,
+    checklist_id: Uuid,
+    tasks: &[Task],
+) -> Result<HashMap<Uuid, Checklist>, ChecklistRepositoryError> {
+    let mut checklists = HashMap::new();
+    let mut pending = Task::included_checklists(tasks);
+    while let Some(id) = pending.pop() {
+        if id == checklist_id {
+            return Err(ChecklistRepositoryError::InvalidChecklist {
+                reason: "checklist cannot include itself, directly or through other checklists"
+                    .to_owned(),
+            });
+        }
+        if checklists.contains_key(&id) {
+            continue;
+        }
+        let checklist = fetch_checklist(pool, None, id)
+            .await
+            .map_err(included_checklist_not_found(id))?;
+        pending.extend(Task::included_checklists(&checklist.tasks));
+        checklists.insert(id, checklist);
+    }
+    Ok(checklists)
+}
+
+async fn insert_version(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    version: i32,
+    checklist: &ChecklistRequest,
+) -> Result<(), ChecklistRepositoryError> {
+    sqlx::query!(
+        r#"
+    INSERT INTO checklist_versions (
+        checklist_id, version, title, variables, tasks, created_by, created_at
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        checklist_id,
+        version,
+        checklist.title,
+        Json(&checklist.variables) as _,
+        Json(&checklist.tasks) as _,
+        user_id,
+        Utc::now()
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create checklist version in database: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Saving new checklist in the database", skip(pool, checklist))]
+pub async fn insert_checklist(
+    pool: &PgPool,
+    owner_id: Uuid,
+    team_id: Option<Uuid>,
+    checklist: &ChecklistRequest,
+) -> Result<Checklist, ChecklistRepositoryError> {
+    validate_checklist(checklist)?;
+    let checklist_id = Uuid::new_v4();
+    check_included_checklists_access(pool, owner_id, &checklist.tasks).await?;
+    fetch_included_checklists(pool, checklist_id, &checklist.tasks).await?;
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+
+    sqlx::query!(
+        r#"
+    INSERT INTO checklists (id, owner_id, team_id, current_version, created_at)
+    VALUES ($1, $2, $3, 1, $4)
+            "#,
+        checklist_id,
+        owner_id,
+        team_id,
+        Utc::now()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create checklist in database: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+    insert_version(&mut transaction, owner_id, checklist_id, 1, checklist).await?;
+
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+
+    get_checklist(pool, owner_id, checklist_id).await
+}
+
+/// Stores `checklist` as a new version and makes it the current one.
+///
+/// Nothing is stored if it is identical to the current version.
+#[tracing::instrument(name = "Updating a checklist in the database", skip(pool, checklist))]
+pub async fn update_checklist(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    checklist: &ChecklistRequest,
+) -> Result<Checklist, ChecklistRepositoryError> {
+    validate_checklist(checklist)?;
+    check_included_checklists_access(pool, user_id, &checklist.tasks).await?;
+    fetch_included_checklists(pool, checklist_id, &checklist.tasks).await?;
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+
+    let current = sqlx::query!(
+        r#"
+    SELECT
+        c.current_version,
+        v.title,
+        v.variables AS "variables: Json<Vec<Variable>>",
+        v.tasks AS "tasks: Json<Vec<Task>>"
+    FROM checklists c
+    JOIN checklist_versions v ON v.checklist_id = c.id AND v.version = c.current_version
+    WHERE c.id = $1 AND EXISTS (
+        SELECT 1 FROM checklist_access a WHERE a.checklist_id = c.id AND a.user_id = $2
+    )
+    FOR UPDATE OF c
+        "#,
+        checklist_id,
+        user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ChecklistRepositoryError::ChecklistNotFound,
+        e => {
+            tracing::error!("Failed to fetch checklist from database: {:?}", e);
+            ChecklistRepositoryError::InternalError
+        }
+    })?;
+
+    if current.title != checklist.title
+        || current.variables.0 != checklist.variables
+        || current.tasks.0 != checklist.tasks
+    {
+        let version = current.current_version + 1;
+        insert_version(&mut transaction, user_id, checklist_id, version, checklist).await?;
+        sqlx::query!(
+            r#"
+    UPDATE checklists
+    SET current_version = $2
+    WHERE id = $1
+            "#,
+            checklist_id,
+            version
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update checklist in database: {:?}", e);
+            ChecklistRepositoryError::InternalError
+        })?;
+    }
+
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+
+    get_checklist(pool, user_id, checklist_id).await
+}
+
+/// Makes the content of an earlier version current again by storing it as a new version.
+#[tracing::instrument(name = "Reverting a checklist to an earlier version", skip(pool))]
+pub async fn revert_checklist(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    version: i32,
+) -> Result<Checklist, ChecklistRepositoryError> {
+    let previous = get_checklist_version(pool, user_id, checklist_id, version).await?;
+    update_checklist(
+        pool,
+        user_id,
+        checklist_id,
+        &ChecklistRequest {
+            title: previous.title,
+            variables: previous.variables,
+            tasks: previous.tasks,
+        },
+    )
+    .await
+}
+
+#[tracing::instrument(name = "Fetching checklists of a user", skip(pool))]
+pub async fn list_checklists(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ChecklistSummary>, ChecklistRepositoryError> {
+    sqlx::query_as!(
+        ChecklistSummary,
+        r#"
+    SELECT c.id, v.title, c.team_id, c.owner_id <> $1 AS "shared!"
+    FROM checklists c
+    JOIN checklist_versions v ON v.checklist_id = c.id AND v.version = c.current_version
+    WHERE EXISTS (
+        SELECT 1 FROM checklist_access a WHERE a.checklist_id = c.id AND a.user_id = $1
+    )
+    ORDER BY c.created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch checklists from database: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })
+}
+
+#[tracing::instrument(name = "Fetching a checklist", skip(pool))]
+pub async fn get_checklist(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+) -> Result<Checklist, ChecklistRepositoryError> {
+    fetch_checklist(pool, Some(user_id), checklist_id).await
+}
+
+/// Fetches the current version of a checklist, only if the user has access to it when one is
+/// given.
+async fn fetch_checklist(
+    pool: &PgPool,
+    user_id: Option<Uuid>,
+    checklist_id: Uuid,
+) -> Result<Checklist, ChecklistRepositoryError> {
+    sqlx::query_as!(
+        ChecklistRow,
+        r#"
+    SELECT
+        c.id,
+        v.version,
+        v.title,
+        v.variables AS "variables: Json<Vec<Variable>>",
+        v.tasks AS "tasks: Json<Vec<Task>>",
+        u.username AS owner,
+        c.team_id
+    FROM checklists c
+    JOIN checklist_versions v ON v.checklist_id = c.id AND v.version = c.current_version
+    JOIN users u ON u.id = c.owner_id
+    WHERE c.id = $1 AND ($2::UUID IS NULL OR EXISTS (
+        SELECT 1 FROM checklist_access a WHERE a.checklist_id = c.id AND a.user_id = $2
+    ))
+        "#,
+        checklist_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map(Checklist::from)
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ChecklistRepositoryError::ChecklistNotFound,
+        e => {
+            tracing::error!("Failed to fetch checklist from database: {:?}", e);
+            ChecklistRepositoryError::InternalError
+        }
+    })
+}
+
+/// Fetches the current version of a checklist with tasks of the included checklists in place
+/// of the includes. Access is only checked for the checklist itself, since includes were checked
+/// when it was saved.
+#[tracing::instrument(name = "Fetching a checklist with included checklists", skip(pool))]
+pub async fn get_resolved_checklist(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+) -> Result<Checklist, ChecklistRepositoryError> {
+    let checklist = get_checklist(pool, user_id, checklist_id).await?;
+    let checklists = fetch_included_checklists(pool, checklist.id, &checklist.tasks).await?;
+    checklist
+        .resolve_includes(&checklists)
+        .map_err(|reason| ChecklistRepositoryError::InvalidChecklist { reason })
+}
+
+#[tracing::instrument(name = "Fetching a version of a checklist", skip(pool))]
+pub async fn get_checklist_version(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    version: i32,
+) -> Result<Checklist, ChecklistRepositoryError> {
+    sqlx::query_as!(
+        ChecklistRow,
+        r#"
+    SELECT
+        c.id,
+        v.version,
+        v.title,
+        v.variables AS "variables: Json<Vec<Variable>>",
+        v.tasks AS "tasks: Json<Vec<Task>>",
+        u.username AS owner,
+        c.team_id
+    FROM checklists c
+    JOIN checklist_versions v ON v.checklist_id = c.id
+    JOIN users u ON u.id = c.owner_id
+    WHERE c.id = $1 AND v.version = $3 AND EXISTS (
+        SELECT 1 FROM checklist_access a WHERE a.checklist_id = c.id AND a.user_id = $2
+    )
+        "#,
+        checklist_id,
+        user_id,
+        version
+    )
+    .fetch_one(pool)
+    .await
+    .map(Checklist::from)
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ChecklistRepositoryError::ChecklistNotFound,
+        e => {
+            tracing::error!("Failed to fetch checklist version from database: {:?}", e);
+            ChecklistRepositoryError::InternalError
+        }
+    })
+}
+
+#[tracing::instrument(name = "Fetching versions of a checklist", skip(pool))]
+pub async fn list_checklist_versions(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+) -> Result<Vec<ChecklistVersionSummary>, ChecklistRepositoryError> {
+    let versions = sqlx::query_as!(
+        ChecklistVersionSummary,
+        r#"
+    SELECT v.version, v.title, v.created_at, u.username AS "created_by?"
+    FROM checklists c
+    JOIN checklist_versions v ON v.checklist_id = c.id
+    LEFT JOIN users u ON u.id = v.created_by
+    WHERE c.id = $1 AND EXISTS (
+        SELECT 1 FROM checklist_access a WHERE a.checklist_id = c.id AND a.user_id = $2
+    )
+    ORDER BY v.version
+        "#,
+        checklist_id,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch checklist versions from database: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+
+    if versions.is_empty() {
+        return Err(ChecklistRepositoryError::ChecklistNotFound);
+    }
+    Ok(versions)
+}
+
+/// Fetches the highest role a user has in a checklist, through ownership, its team or a grant.
+#[tracing::instrument(name = "Fetching role of a user in a checklist", skip(pool))]
+pub async fn get_checklist_role(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+) -> Result<Role, ChecklistRepositoryError> {
+    let rows = sqlx::query!(
+        r#"
+    SELECT role AS "role!" FROM checklist_access
+    WHERE checklist_id = $1 AND user_id = $2
+        "#,
+        checklist_id,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch checklist access from database: {:?}", e);
+        ChecklistRepositoryError::InternalError
+    })?;
+
+    let mut role = None;
+    for row in rows {
+        let row_role = Role::from_str(&row.role).map_err(|_| {
+            tracing::error!("Invalid role in database: {}", row.role);
+            ChecklistRepositoryError::InternalError
+        })?;
+        role = role.max(Some(row_role));
+    }
+    role.ok_or(ChecklistRepositoryError::ChecklistNotFound)
+}
@@ -0,0 +1,278 @@
+use crate::{
+    controller::execution_repository::{self, ExecutionRepositoryError},
+    models::{
+        comment::{Comment, CommentCounts},
+        execution::TaskRef,
+    },
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CommentRepositoryError {
+    #[error("execution not found")]
+    ExecutionNotFound,
+    #[error("task not found")]
+    TaskNotFound,
+    #[error("comment not found")]
+    CommentNotFound,
+    #[error("only the author can change a comment")]
+    NotAuthor,
+    #[error("comment cannot be empty")]
+    EmptyComment,
+    #[error("internal error")]
+    InternalError,
+}
+
+struct CommentRow {
+    id: Uuid,
+    task_id: Option<Uuid>,
+    author: String,
+    body: String,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<CommentRow> for Comment {
+    fn from(row: CommentRow) -> Self {
+        Comment {
+            id: row.id,
+            task_id: row.task_id,
+            author: row.author,
+            body: row.body,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+fn check_body(body: &str) -> Result<(), CommentRepositoryError> {
+    if body.trim().is_empty() {
+        Err(CommentRepositoryError::EmptyComment)
+    } else {
+        Ok(())
+    }
+}
+
+/// Resolves the task a comment is about, making sure the user has access to the execution and
+/// that the task is part of it.
+async fn resolve_task(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    task: Option<TaskRef>,
+) -> Result<Option<Uuid>, CommentRepositoryError> {
+    let execution = execution_repository::get_execution(pool, user_id, execution_id)
+        .await
+        .map_err(|e| match e {
+            ExecutionRepositoryError::ExecutionNotFound => {
+                CommentRepositoryError::ExecutionNotFound
+            }
+            _ => CommentRepositoryError::InternalError,
+        })?;
+    task.map(|task| execution.task_id(task))
+        .transpose()
+        .map_err(|_| CommentRepositoryError::TaskNotFound)
+}
+
+/// Adds a comment on one of the execution's tasks, or on the whole execution without a task.
+#[tracing::instrument(name = "Saving new comment in the database", skip(pool, body))]
+pub async fn insert_comment(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    task: Option<TaskRef>,
+    body: &str,
+) -> Result<Comment, CommentRepositoryError> {
+    check_body(body)?;
+    let task_id = resolve_task(pool, user_id, execution_id, task).await?;
+
+    sqlx::query_as!(
+        CommentRow,
+        r#"
+    WITH comment AS (
+        INSERT INTO comments (id, execution_id, task_id, author_id, body, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, task_id, author_id, body, created_at, updated_at
+    )
+    SELECT c.id, c.task_id, u.username AS author, c.body, c.created_at, c.updated_at
+    FROM comment c
+    JOIN users u ON u.id = c.author_id
+            "#,
+        Uuid::new_v4(),
+        execution_id,
+        task_id,
+        user_id,
+        body,
+        Utc::now()
+    )
+    .fetch_one(pool)
+    .await
+    .map(Comment::from)
+    .map_err(|e| {
+        tracing::error!("Failed to save comment in database: {:?}", e);
+        CommentRepositoryError::InternalError
+    })
+}
+
+/// Lists comments of an execution, including comments on its tasks, or only of one task.
+#[tracing::instrument(name = "Fetching comments of an execution", skip(pool))]
+pub async fn list_comments(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    task: Option<TaskRef>,
+) -> Result<Vec<Comment>, CommentRepositoryError> {
+    let task_id = resolve_task(pool, user_id, execution_id, task).await?;
+
+    sqlx::query_as!(
+        CommentRow,
+        r#"
+    SELECT c.id, c.task_id, u.username AS author, c.body, c.created_at, c.updated_at
+    FROM comments c
+    JOIN users u ON u.id = c.author_id
+    WHERE c.execution_id = $1 AND ($2::UUID IS NULL OR c.task_id = $2)
+    ORDER BY c.created_at
+        "#,
+        execution_id,
+        task_id
+    )
+    .fetch_all(pool)
+    .await
+    .map(|rows| rows.into_iter().map(Comment::from).collect())
+    .map_err(|e| {
+        tracing::error!("Failed to fetch comments from database: {:?}", e);
+        CommentRepositoryError::InternalError
+    })
+}
+
+#[tracing::instrument(name = "Counting comments of an execution", skip(pool))]
+pub async fn count_comments(
+    pool: &PgPool,
+    execution_id: Uuid,
+) -> Result<CommentCounts, CommentRepositoryError> {
+    let rows = sqlx::query!(
+        r#"
+    SELECT task_id, COUNT(*) AS "count!"
+    FROM comments
+    WHERE execution_id = $1
+    GROUP BY task_id
+        "#,
+        execution_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count comments in database: {:?}", e);
+        CommentRepositoryError::InternalError
+    })?;
+
+    let mut counts = CommentCounts::default();
+    for row in rows {
+        counts.total += row.count;
+        if let Some(task_id) = row.task_id {
+            counts.tasks.insert(task_id, row.count);
+        }
+    }
+    Ok(counts)
+}
+
+/// Checks that the user can read the execution, and that the comment is part of it and was
+/// written by the user.
+async fn check_author(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    comment_id: Uuid,
+) -> Result<(), CommentRepositoryError> {
+    resolve_task(pool, user_id, execution_id, None).await?;
+    let author_id = sqlx::query!(
+        r#"
+    SELECT author_id FROM comments
+    WHERE id = $1 AND execution_id = $2
+        "#,
+        comment_id,
+        execution_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => CommentRepositoryError::CommentNotFound,
+        e => {
+            tracing::error!("Failed to fetch comment from database: {:?}", e);
+            CommentRepositoryError::InternalError
+        }
+    })?
+    .author_id;
+
+    if author_id == user_id {
+        Ok(())
+    } else {
+        Err(CommentRepositoryError::NotAuthor)
+    }
+}
+
+#[tracing::instrument(name = "Updating comment in the database", skip(pool, body))]
+pub async fn update_comment(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    comment_id: Uuid,
+    body: &str,
+) -> Result<Comment, CommentRepositoryError> {
+    check_body(body)?;
+    check_author(pool, user_id, execution_id, comment_id).await?;
+
+    sqlx::query_as!(
+        CommentRow,
+        r#"
+    WITH comment AS (
+        UPDATE comments SET body = $2, updated_at = $3
+        WHERE id = $1
+        RETURNING id, task_id, author_id, body, created_at, updated_at
+    )
+    SELECT c.id, c.task_id, u.username AS author, c.body, c.created_at, c.updated_at
+    FROM comment c
+    JOIN users u ON u.id = c.author_id
+            "#,
+        comment_id,
+        body,
+        Utc::now()
+    )
+    .fetch_one(pool)
+    .await
+    .map(Comment::from)
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => CommentRepositoryError::CommentNotFound,
+        e => {
+            tracing::error!("Failed to update comment in database: {:?}", e);
+            CommentRepositoryError::InternalError
+        }
+    })
+}
+
+#[tracing::instrument(name = "Deleting comment from the database", skip(pool))]
+pub async fn delete_comment(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    comment_id: Uuid,
+) -> Result<(), CommentRepositoryError> {
+    check_author(pool, user_id, execution_id, comment_id).await?;
+
+    sqlx::query!(
+        r#"
+    DELETE FROM comments
+    WHERE id = $1
+            "#,
+        comment_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete comment from database: {:?}", e);
+        CommentRepositoryError::InternalError
+    })?;
+    Ok(())
+}
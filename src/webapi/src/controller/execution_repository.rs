@@ -0,0 +1,680 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::{
+    controller::{
+        checklist_repository::{self, ChecklistRepositoryError},
+        comment_repository,
+        user_repository::{self, UserRepositoryError},
+    },
+    models::{
+        checklist::VariableValue,
+        comment::CommentCounts,
+        execution::{
+            AssignedTask, Assignment, Execution, ExecutionSummary, Progress, Signature, State,
+            TaskRef, TaskStatus, TaskUpdate, TransitionError,
+        },
+        team::Role,
+    },
+    routes::execution::SignatureRequest,
+};
+use chrono::Utc;
+use sqlx::{types::Json, PgExecutor, PgPool};
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExecutionRepositoryError {
+    #[error("checklist not found")]
+    ChecklistNotFound,
+    #[error("execution not found")]
+    ExecutionNotFound,
+    #[error("invalid variables: {reason}")]
+    InvalidVariables { reason: String },
+    #[error("invalid checklist: {reason}")]
+    InvalidChecklist { reason: String },
+    #[error("user '{username}' not found")]
+    UserNotFound { username: String },
+    #[error("user '{username}' cannot execute tasks of the execution")]
+    InvalidAssignee { username: String },
+    #[error("invalid password")]
+    InvalidPassword,
+    #[error(transparent)]
+    Transition(#[from] TransitionError),
+    #[error("internal error")]
+    InternalError,
+}
+
+struct ExecutionRow {
+    id: Uuid,
+    checklist_id: Uuid,
+    checklist_version: i32,
+    title: String,
+    variables: Json<BTreeMap<String, VariableValue>>,
+    batch_id: Option<Uuid>,
+    target: Option<String>,
+    team_id: Option<Uuid>,
+    task_statuses: Json<Vec<TaskStatus>>,
+    state: String,
+    reason: Option<String>,
+}
+
+impl TryFrom<ExecutionRow> for Execution {
+    type Error = ExecutionRepositoryError;
+
+    fn try_from(row: ExecutionRow) -> Result<Self, Self::Error> {
+        let mut execution = Execution {
+            id: row.id,
+            checklist_id: row.checklist_id,
+            checklist_version: row.checklist_version,
+            title: row.title,
+            variables: row.variables.0,
+            batch_id: row.batch_id,
+            target: row.target,
+            team_id: row.team_id,
+            task_statuses: row.task_statuses.0,
+            state: parse_state(&row.state)?,
+            reason: row.reason,
+            progress: Progress::default(),
+            comments: CommentCounts::default(),
+        };
+        execution.refresh();
+        Ok(execution)
+    }
+}
+
+fn parse_state(state: &str) -> Result<State, ExecutionRepositoryError> {
+    State::from_str(state).map_err(|_| {
+        tracing::error!("Invalid execution state in database: {}", state);
+        ExecutionRepositoryError::InternalError
+    })
+}
+
+/// Execution of a checklist ready to be stored for one or more targets.
+pub struct NewExecution {
+    checklist_id: Uuid,
+    checklist_version: i32,
+    title: String,
+    variables: BTreeMap<String, VariableValue>,
+    team_id: Option<Uuid>,
+    task_statuses: Vec<TaskStatus>,
+}
+
+impl NewExecution {
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn team_id(&self) -> Option<Uuid> {
+        self.team_id
+    }
+}
+
+/// Resolves the current version of a checklist and renders it with `variables`.
+pub async fn prepare_execution(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    variables: &BTreeMap<String, VariableValue>,
+) -> Result<NewExecution, ExecutionRepositoryError> {
+    let checklist = checklist_repository::get_resolved_checklist(pool, user_id, checklist_id)
+        .await
+        .map_err(|e| match e {
+            ChecklistRepositoryError::ChecklistNotFound => {
+                ExecutionRepositoryError::ChecklistNotFound
+            }
+            ChecklistRepositoryError::InvalidChecklist { reason } => {
+                ExecutionRepositoryError::InvalidChecklist { reason }
+            }
+            _ => ExecutionRepositoryError::InternalError,
+        })?;
+    let (title, tasks) = checklist
+        .render(variables)
+        .map_err(|reason| ExecutionRepositoryError::InvalidVariables { reason })?;
+
+    Ok(NewExecution {
+        checklist_id: checklist.id,
+        checklist_version: checklist.version,
+        title,
+        variables: variables.clone(),
+        team_id: checklist.team_id,
+        task_statuses: TaskStatus::from_tasks(&tasks, variables),
+    })
+}
+
+pub async fn insert_execution<'c>(
+    executor: impl PgExecutor<'c>,
+    user_id: Uuid,
+    execution: &NewExecution,
+    batch_id: Option<Uuid>,
+    target: Option<&str>,
+) -> Result<Uuid, ExecutionRepositoryError> {
+    let execution_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+    INSERT INTO executions (
+        id, checklist_id, checklist_version, owner_id, team_id, title, variables, task_statuses,
+        state, batch_id, target, created_at
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#,
+        execution_id,
+        execution.checklist_id,
+        execution.checklist_version,
+        user_id,
+        execution.team_id,
+        execution.title,
+        Json(&execution.variables) as _,
+        Json(&execution.task_statuses) as _,
+        State::NotStarted.as_str(),
+        batch_id,
+        target,
+        Utc::now()
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create execution in database: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?;
+
+    Ok(execution_id)
+}
+
+#[tracing::instrument(name = "Starting execution of a checklist", skip(pool))]
+pub async fn start_execution(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    variables: &BTreeMap<String, VariableValue>,
+) -> Result<Uuid, ExecutionRepositoryError> {
+    let execution = prepare_execution(pool, user_id, checklist_id, variables).await?;
+    insert_execution(pool, user_id, &execution, None, None).await
+}
+
+#[tracing::instrument(name = "Fetching executions of a user", skip(pool))]
+pub async fn list_executions(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ExecutionSummary>, ExecutionRepositoryError> {
+    let rows = sqlx::query!(
+        r#"
+    SELECT e.id, e.checklist_id, e.title, e.state FROM executions e
+    WHERE EXISTS (
+        SELECT 1 FROM execution_access a WHERE a.execution_id = e.id AND a.user_id = $1
+    )
+    ORDER BY e.created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch executions from database: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(ExecutionSummary {
+                id: row.id,
+                checklist_id: row.checklist_id,
+                title: row.title,
+                state: parse_state(&row.state)?,
+            })
+        })
+        .collect()
+}
+
+#[tracing::instrument(name = "Fetching an execution", skip(pool))]
+pub async fn get_execution(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+) -> Result<Execution, ExecutionRepositoryError> {
+    let mut execution: Execution = sqlx::query_as!(
+        ExecutionRow,
+        r#"
+    SELECT
+        id,
+        checklist_id,
+        checklist_version,
+        title,
+        variables AS "variables: Json<BTreeMap<String, VariableValue>>",
+        batch_id,
+        target,
+        team_id,
+        task_statuses AS "task_statuses: Json<Vec<TaskStatus>>",
+        state,
+        reason
+    FROM executions e
+    WHERE id = $1 AND EXISTS (
+        SELECT 1 FROM execution_access a WHERE a.execution_id = e.id AND a.user_id = $2
+    )
+        "#,
+        execution_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ExecutionRepositoryError::ExecutionNotFound,
+        e => {
+            tracing::error!("Failed to fetch execution from database: {:?}", e);
+            ExecutionRepositoryError::InternalError
+        }
+    })?
+    .try_into()?;
+    execution.comments = comment_repository::count_comments(pool, execution_id)
+        .await
+        .map_err(|_| ExecutionRepositoryError::InternalError)?;
+    Ok(execution)
+}
+
+/// Fetches an execution through a share link that has not expired, without a logged in user.
+#[tracing::instrument(name = "Fetching a shared execution", skip(pool, token_hash))]
+pub async fn get_shared_execution(
+    pool: &PgPool,
+    token_hash: &[u8],
+) -> Result<Execution, ExecutionRepositoryError> {
+    sqlx::query_as!(
+        ExecutionRow,
+        r#"
+    SELECT
+        e.id,
+        e.checklist_id,
+        e.checklist_version,
+        e.title,
+        e.variables AS "variables: Json<BTreeMap<String, VariableValue>>",
+        e.batch_id,
+        e.target,
+        e.team_id,
+        e.task_statuses AS "task_statuses: Json<Vec<TaskStatus>>",
+        e.state,
+        e.reason
+    FROM executions e
+    JOIN share_links l ON l.execution_id = e.id
+    WHERE l.token_hash = $1 AND (l.expires_at IS NULL OR l.expires_at > $2)
+        "#,
+        token_hash,
+        Utc::now()
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ExecutionRepositoryError::ExecutionNotFound,
+        e => {
+            tracing::error!("Failed to fetch execution from database: {:?}", e);
+            ExecutionRepositoryError::InternalError
+        }
+    })?
+    .try_into()
+}
+
+#[tracing::instrument(name = "Fetching executions of a batch", skip(pool))]
+pub async fn list_batch_executions(
+    pool: &PgPool,
+    batch_id: Uuid,
+) -> Result<Vec<Execution>, ExecutionRepositoryError> {
+    sqlx::query_as!(
+        ExecutionRow,
+        r#"
+    SELECT
+        id,
+        checklist_id,
+        checklist_version,
+        title,
+        variables AS "variables: Json<BTreeMap<String, VariableValue>>",
+        batch_id,
+        target,
+        team_id,
+        task_statuses AS "task_statuses: Json<Vec<TaskStatus>>",
+        state,
+        reason
+    FROM executions
+    WHERE batch_id = $1
+    ORDER BY target
+        "#,
+        batch_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch executions from database: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?
+    .into_iter()
+    .map(Execution::try_from)
+    .collect()
+}
+
+/// Fetches the highest role a user has in an execution, through ownership or its team.
+#[tracing::instrument(name = "Fetching role of a user in an execution", skip(pool))]
+pub async fn get_execution_role(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+) -> Result<Role, ExecutionRepositoryError> {
+    let rows = sqlx::query!(
+        r#"
+    SELECT role AS "role!" FROM execution_access
+    WHERE execution_id = $1 AND user_id = $2
+        "#,
+        execution_id,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch execution access from database: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?;
+
+    let mut role = None;
+    for row in rows {
+        let row_role = Role::from_str(&row.role).map_err(|_| {
+            tracing::error!("Invalid role in database: {}", row.role);
+            ExecutionRepositoryError::InternalError
+        })?;
+        role = role.max(Some(row_role));
+    }
+    role.ok_or(ExecutionRepositoryError::ExecutionNotFound)
+}
+
+/// Checks whether the user started the execution, as opposed to having access through its team.
+#[tracing::instrument(name = "Checking owner of an execution", skip(pool))]
+pub async fn is_execution_owner(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+) -> Result<bool, ExecutionRepositoryError> {
+    sqlx::query!(
+        r#"
+    SELECT owner_id FROM executions
+    WHERE id = $1
+        "#,
+        execution_id
+    )
+    .fetch_one(pool)
+    .await
+    .map(|row| row.owner_id == user_id)
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ExecutionRepositoryError::ExecutionNotFound,
+        e => {
+            tracing::error!("Failed to fetch execution from database: {:?}", e);
+            ExecutionRepositoryError::InternalError
+        }
+    })
+}
+
+/// Applies `update` to an execution while holding a lock on its row and saves the result.
+async fn update_execution<T>(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    update: impl FnOnce(&mut Execution) -> Result<T, TransitionError>,
+) -> Result<(T, Execution), ExecutionRepositoryError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?;
+
+    let mut execution: Execution = sqlx::query_as!(
+        ExecutionRow,
+        r#"
+    SELECT
+        id,
+        checklist_id,
+        checklist_version,
+        title,
+        variables AS "variables: Json<BTreeMap<String, VariableValue>>",
+        batch_id,
+        target,
+        team_id,
+        task_statuses AS "task_statuses: Json<Vec<TaskStatus>>",
+        state,
+        reason
+    FROM executions e
+    WHERE id = $1 AND EXISTS (
+        SELECT 1 FROM execution_access a WHERE a.execution_id = e.id AND a.user_id = $2
+    )
+    FOR UPDATE
+        "#,
+        execution_id,
+        user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ExecutionRepositoryError::ExecutionNotFound,
+        e => {
+            tracing::error!("Failed to fetch execution from database: {:?}", e);
+            ExecutionRepositoryError::InternalError
+        }
+    })?
+    .try_into()?;
+
+    let result = update(&mut execution)?;
+
+    sqlx::query!(
+        r#"
+    UPDATE executions
+    SET task_statuses = $2, state = $3, reason = $4
+    WHERE id = $1
+        "#,
+        execution.id,
+        Json(&execution.task_statuses) as _,
+        execution.state.as_str(),
+        execution.reason
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update execution in database: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?;
+
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?;
+
+    Ok((result, execution))
+}
+
+async fn get_username(pool: &PgPool, user_id: Uuid) -> Result<String, ExecutionRepositoryError> {
+    sqlx::query!(
+        r#"
+    SELECT username FROM users
+    WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map(|row| row.username)
+    .map_err(|e| {
+        tracing::error!("Failed to fetch user from database: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })
+}
+
+/// Changes state of a task; unless `any_task` is set, tasks assigned to other users are
+/// rejected.
+#[tracing::instrument(
+    name = "Updating state of an execution task",
+    skip(pool, update, signature)
+)]
+pub async fn update_task_state(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    task: TaskRef,
+    update: TaskUpdate,
+    signature: Option<SignatureRequest>,
+    any_task: bool,
+) -> Result<TaskStatus, ExecutionRepositoryError> {
+    let (username, signature) = match signature {
+        Some(signature) => {
+            let username = user_repository::reauthenticate(pool, user_id, &signature.password)
+                .await
+                .map_err(|e| match e {
+                    UserRepositoryError::InvalidUserOrPassword => {
+                        ExecutionRepositoryError::InvalidPassword
+                    }
+                    _ => ExecutionRepositoryError::InternalError,
+                })?;
+            let signature = Signature {
+                username: username.clone(),
+                meaning: signature.meaning,
+                signed_at: Utc::now(),
+            };
+            (username, Some(signature))
+        }
+        None => (get_username(pool, user_id).await?, None),
+    };
+    let (task_status, _) = update_execution(pool, user_id, execution_id, |execution| {
+        if let (false, Some(assignee)) = (any_task, execution.assignee_of(task)?) {
+            if assignee != username {
+                return Err(TransitionError::NotAssignee {
+                    assignee: assignee.to_owned(),
+                });
+            }
+        }
+        match signature {
+            Some(signature) => execution.update_signed_task_state(task, update, signature),
+            None => execution.update_task_state(task, update, &username),
+        }
+    })
+    .await?;
+    Ok(task_status)
+}
+
+#[tracing::instrument(name = "Verifying an execution task", skip(pool))]
+pub async fn verify_task(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    task: TaskRef,
+) -> Result<TaskStatus, ExecutionRepositoryError> {
+    let username = get_username(pool, user_id).await?;
+    let (task_status, _) = update_execution(pool, user_id, execution_id, |execution| {
+        execution.verify_task(task, &username)
+    })
+    .await?;
+    Ok(task_status)
+}
+
+/// Assigns a task to a user that can execute tasks of the execution, or unassigns it.
+#[tracing::instrument(name = "Assigning an execution task", skip(pool))]
+pub async fn assign_task(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    task: TaskRef,
+    assignment: Assignment,
+) -> Result<TaskStatus, ExecutionRepositoryError> {
+    if let Some(username) = &assignment.assignee {
+        let assignee_id = sqlx::query!(
+            r#"
+    SELECT id FROM users
+    WHERE username = $1
+            "#,
+            username
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => ExecutionRepositoryError::UserNotFound {
+                username: username.clone(),
+            },
+            e => {
+                tracing::error!("Failed to fetch user from database: {:?}", e);
+                ExecutionRepositoryError::InternalError
+            }
+        })?
+        .id;
+        match get_execution_role(pool, assignee_id, execution_id).await {
+            Ok(role) if role.can_execute() => {}
+            Ok(_) | Err(ExecutionRepositoryError::ExecutionNotFound) => {
+                return Err(ExecutionRepositoryError::InvalidAssignee {
+                    username: username.clone(),
+                })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let (task_status, _) = update_execution(pool, user_id, execution_id, |execution| {
+        execution.assign_task(task, assignment)
+    })
+    .await?;
+    Ok(task_status)
+}
+
+/// Lists open tasks assigned to a user across unfinished executions, earliest due first.
+#[tracing::instrument(name = "Fetching tasks assigned to a user", skip(pool))]
+pub async fn list_assigned_tasks(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<AssignedTask>, ExecutionRepositoryError> {
+    let username = get_username(pool, user_id).await?;
+    let executions = sqlx::query_as!(
+        ExecutionRow,
+        r#"
+    SELECT
+        id,
+        checklist_id,
+        checklist_version,
+        title,
+        variables AS "variables: Json<BTreeMap<String, VariableValue>>",
+        batch_id,
+        target,
+        team_id,
+        task_statuses AS "task_statuses: Json<Vec<TaskStatus>>",
+        state,
+        reason
+    FROM executions e
+    WHERE jsonb_path_exists(
+        e.task_statuses, '$.**.assignee ? (@ == $username)', jsonb_build_object('username', $2::TEXT)
+    )
+    AND EXISTS (
+        SELECT 1 FROM execution_access a WHERE a.execution_id = e.id AND a.user_id = $1
+    )
+    ORDER BY e.created_at
+        "#,
+        user_id,
+        username
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch executions from database: {:?}", e);
+        ExecutionRepositoryError::InternalError
+    })?;
+
+    let mut tasks = Vec::new();
+    for row in executions {
+        let execution = Execution::try_from(row)?;
+        if execution.state.is_finished() {
+            continue;
+        }
+        tasks.extend(execution.assigned_tasks(&username));
+    }
+    // Tasks without a due date come last.
+    tasks.sort_by_key(|t| (t.due_at.is_none(), t.due_at));
+    Ok(tasks)
+}
+
+#[tracing::instrument(name = "Updating state of an execution", skip(pool, reason))]
+pub async fn update_execution_state(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    state: State,
+    reason: Option<String>,
+) -> Result<Execution, ExecutionRepositoryError> {
+    let (_, execution) = update_execution(pool, user_id, execution_id, |execution| {
+        execution.update_state(state, reason)
+    })
+    .await?;
+    Ok(execution)
+}
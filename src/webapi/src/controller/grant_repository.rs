@@ -0,0 +1,145 @@
+use crate::models::grant::{Access, Grant};
+use chrono::Utc;
+use sqlx::PgPool;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GrantRepositoryError {
+    #[error("user '{username}' not found")]
+    UserNotFound { username: String },
+    #[error("checklist is not shared with user '{username}'")]
+    GrantNotFound { username: String },
+    #[error("checklist cannot be shared with its owner")]
+    OwnerGrant,
+    #[error("internal error")]
+    InternalError,
+}
+
+fn parse_access(access: &str) -> Result<Access, GrantRepositoryError> {
+    Access::from_str(access).map_err(|_| {
+        tracing::error!("Invalid checklist access in database: {}", access);
+        GrantRepositoryError::InternalError
+    })
+}
+
+/// Grants a user access to a checklist, replacing access granted to them before.
+#[tracing::instrument(name = "Granting access to a checklist", skip(pool))]
+pub async fn set_grant(
+    pool: &PgPool,
+    checklist_id: Uuid,
+    username: &str,
+    access: Access,
+) -> Result<Grant, GrantRepositoryError> {
+    let user = sqlx::query!(
+        r#"
+    SELECT u.id, u.id = c.owner_id AS "is_owner!"
+    FROM users u, checklists c
+    WHERE u.username = $1 AND c.id = $2
+        "#,
+        username,
+        checklist_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => GrantRepositoryError::UserNotFound {
+            username: username.to_owned(),
+        },
+        e => {
+            tracing::error!("Failed to fetch user from database: {:?}", e);
+            GrantRepositoryError::InternalError
+        }
+    })?;
+    if user.is_owner {
+        return Err(GrantRepositoryError::OwnerGrant);
+    }
+
+    let created_at = sqlx::query!(
+        r#"
+    INSERT INTO checklist_grants (checklist_id, user_id, access, created_at)
+    VALUES ($1, $2, $3, $4)
+    ON CONFLICT (checklist_id, user_id) DO UPDATE SET access = EXCLUDED.access
+    RETURNING created_at
+            "#,
+        checklist_id,
+        user.id,
+        access.as_str(),
+        Utc::now()
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save checklist grant in database: {:?}", e);
+        GrantRepositoryError::InternalError
+    })?
+    .created_at;
+
+    Ok(Grant {
+        username: username.to_owned(),
+        access,
+        created_at,
+    })
+}
+
+#[tracing::instrument(name = "Fetching grants of a checklist", skip(pool))]
+pub async fn list_grants(
+    pool: &PgPool,
+    checklist_id: Uuid,
+) -> Result<Vec<Grant>, GrantRepositoryError> {
+    sqlx::query!(
+        r#"
+    SELECT u.username, g.access, g.created_at
+    FROM checklist_grants g
+    JOIN users u ON u.id = g.user_id
+    WHERE g.checklist_id = $1
+    ORDER BY g.created_at
+        "#,
+        checklist_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch checklist grants from database: {:?}", e);
+        GrantRepositoryError::InternalError
+    })?
+    .into_iter()
+    .map(|row| {
+        Ok(Grant {
+            username: row.username,
+            access: parse_access(&row.access)?,
+            created_at: row.created_at,
+        })
+    })
+    .collect()
+}
+
+#[tracing::instrument(name = "Revoking access to a checklist", skip(pool))]
+pub async fn revoke_grant(
+    pool: &PgPool,
+    checklist_id: Uuid,
+    username: &str,
+) -> Result<(), GrantRepositoryError> {
+    let revoked = sqlx::query!(
+        r#"
+    DELETE FROM checklist_grants g
+    USING users u
+    WHERE g.checklist_id = $1 AND g.user_id = u.id AND u.username = $2
+            "#,
+        checklist_id,
+        username
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete checklist grant from database: {:?}", e);
+        GrantRepositoryError::InternalError
+    })?
+    .rows_affected();
+    if revoked == 0 {
+        return Err(GrantRepositoryError::GrantNotFound {
+            username: username.to_owned(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,424 @@
+use crate::{
+    controller::user_repository::{self, UserRepositoryError},
+    models::mfa::{
+        generate_recovery_codes, hash_recovery_code, MfaStatus, SecondFactor, TotpEnrolment,
+        TotpSecret,
+    },
+};
+use chrono::Utc;
+use secrecy::{ExposeSecret, SecretString};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Second factors are locked out after this many failed attempts in a row, across all logins of
+/// the user, so that codes cannot be guessed by starting new logins.
+const MAX_FAILED_ATTEMPTS: i32 = 10;
+const LOCKOUT_MINUTES: i64 = 15;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MfaRepositoryError {
+    #[error("authenticator is already enabled")]
+    AlreadyEnabled,
+    #[error("no authenticator is waiting for confirmation")]
+    NotEnrolled,
+    #[error("authenticator is not enabled")]
+    NotEnabled,
+    #[error("invalid authentication code")]
+    InvalidCode,
+    #[error("invalid password")]
+    InvalidPassword,
+    #[error("too many failed authentication codes, try again later")]
+    Locked,
+    #[error("internal error")]
+    InternalError,
+}
+
+async fn check_password(
+    pool: &PgPool,
+    user_id: Uuid,
+    password: &SecretString,
+) -> Result<(), MfaRepositoryError> {
+    user_repository::reauthenticate(pool, user_id, password)
+        .await
+        .map(|_| ())
+        .map_err(|e| match e {
+            UserRepositoryError::InvalidUserOrPassword => MfaRepositoryError::InvalidPassword,
+            _ => MfaRepositoryError::InternalError,
+        })
+}
+
+/// Provisions a new authenticator secret after checking the user's password, replacing one that
+/// was not confirmed yet.
+#[tracing::instrument(name = "Enrolling TOTP authenticator", skip(pool, password))]
+pub async fn start_totp_enrolment(
+    pool: &PgPool,
+    user_id: Uuid,
+    password: &SecretString,
+) -> Result<TotpEnrolment, MfaRepositoryError> {
+    check_password(pool, user_id, password).await?;
+    let secret = TotpSecret::generate_new();
+
+    let username = sqlx::query!(
+        r#"
+    WITH enrolment AS (
+        INSERT INTO totp_authenticators (user_id, secret, created_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id) DO UPDATE
+        SET secret = EXCLUDED.secret, created_at = EXCLUDED.created_at
+        WHERE totp_authenticators.confirmed_at IS NULL
+        RETURNING user_id
+    )
+    SELECT u.username
+    FROM enrolment e
+    JOIN users u ON u.id = e.user_id
+            "#,
+        user_id,
+        secret.to_database_value(),
+        Utc::now()
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => MfaRepositoryError::AlreadyEnabled,
+        _ => {
+            tracing::error!("Failed to save authenticator in database: {:?}", e);
+            MfaRepositoryError::InternalError
+        }
+    })?
+    .username;
+
+    Ok(TotpEnrolment {
+        secret: secret.to_base32().expose_secret().clone(),
+        otpauth_uri: secret.otpauth_uri(&username).expose_secret().clone(),
+    })
+}
+
+/// Replaces all recovery codes of the user with new ones.
+async fn replace_recovery_codes(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<Vec<SecretString>, MfaRepositoryError> {
+    let codes = generate_recovery_codes();
+    let hashes: Vec<Vec<u8>> = codes.iter().map(hash_recovery_code).collect();
+
+    sqlx::query!(
+        r#"
+    DELETE FROM recovery_codes
+    WHERE user_id = $1
+            "#,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete recovery codes from database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+
+    sqlx::query!(
+        r#"
+    INSERT INTO recovery_codes (user_id, code_hash)
+    SELECT $1, * FROM UNNEST($2::bytea[])
+            "#,
+        user_id,
+        &hashes
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save recovery codes in database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+    Ok(codes)
+}
+
+/// Enables the authenticator once the user proves it was set up by entering a code, and
+/// generates recovery codes for when it is lost.
+#[tracing::instrument(name = "Confirming TOTP authenticator", skip(pool, code))]
+pub async fn confirm_totp_enrolment(
+    pool: &PgPool,
+    user_id: Uuid,
+    code: &str,
+) -> Result<Vec<SecretString>, MfaRepositoryError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+
+    let secret = sqlx::query!(
+        r#"
+    SELECT secret FROM totp_authenticators
+    WHERE user_id = $1 AND confirmed_at IS NULL
+    FOR UPDATE
+            "#,
+        user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => MfaRepositoryError::NotEnrolled,
+        _ => {
+            tracing::error!("Failed to fetch authenticator from database: {:?}", e);
+            MfaRepositoryError::InternalError
+        }
+    })?
+    .secret;
+
+    let now = Utc::now();
+    let step = TotpSecret::from_bytes(secret)
+        .verify(code, now, None)
+        .ok_or(MfaRepositoryError::InvalidCode)?;
+
+    sqlx::query!(
+        r#"
+    UPDATE totp_authenticators SET confirmed_at = $2, last_used_step = $3
+    WHERE user_id = $1
+            "#,
+        user_id,
+        now,
+        step
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to confirm authenticator in database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+
+    let codes = replace_recovery_codes(&mut transaction, user_id).await?;
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+    Ok(codes)
+}
+
+/// Replaces recovery codes of a user with an enabled authenticator, e.g. after using some, after
+/// checking the user's password.
+#[tracing::instrument(name = "Regenerating recovery codes", skip(pool, password))]
+pub async fn regenerate_recovery_codes(
+    pool: &PgPool,
+    user_id: Uuid,
+    password: &SecretString,
+) -> Result<Vec<SecretString>, MfaRepositoryError> {
+    check_password(pool, user_id, password).await?;
+    if !is_enabled(pool, user_id).await? {
+        return Err(MfaRepositoryError::NotEnabled);
+    }
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+    let codes = replace_recovery_codes(&mut transaction, user_id).await?;
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+    Ok(codes)
+}
+
+/// Removes the authenticator and recovery codes after checking the user's password.
+#[tracing::instrument(name = "Disabling TOTP authenticator", skip(pool, password))]
+pub async fn disable_totp(
+    pool: &PgPool,
+    user_id: Uuid,
+    password: &SecretString,
+) -> Result<(), MfaRepositoryError> {
+    check_password(pool, user_id, password).await?;
+
+    let deleted = sqlx::query!(
+        r#"
+    WITH codes AS (
+        DELETE FROM recovery_codes
+        WHERE user_id = $1
+    )
+    DELETE FROM totp_authenticators
+    WHERE user_id = $1
+            "#,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete authenticator from database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?
+    .rows_affected();
+
+    if deleted == 0 {
+        Err(MfaRepositoryError::NotEnabled)
+    } else {
+        Ok(())
+    }
+}
+
+#[tracing::instrument(name = "Fetching MFA status", skip(pool))]
+pub async fn get_mfa_status(pool: &PgPool, user_id: Uuid) -> Result<MfaStatus, MfaRepositoryError> {
+    sqlx::query_as!(
+        MfaStatus,
+        r#"
+    SELECT
+        EXISTS(
+            SELECT 1 FROM totp_authenticators
+            WHERE user_id = $1 AND confirmed_at IS NOT NULL
+        ) AS "totp_enabled!",
+        (SELECT COUNT(*) FROM recovery_codes WHERE user_id = $1) AS "recovery_codes_left!"
+            "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch MFA status from database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })
+}
+
+/// Whether the user has a confirmed authenticator, and so needs a second factor to log in.
+pub async fn is_enabled(pool: &PgPool, user_id: Uuid) -> Result<bool, MfaRepositoryError> {
+    get_mfa_status(pool, user_id)
+        .await
+        .map(|status| status.totp_enabled)
+}
+
+/// Refuses second factors of users with too many failed attempts until the lockout ends.
+#[tracing::instrument(name = "Checking second factor lockout", skip(pool))]
+pub async fn check_not_locked(pool: &PgPool, user_id: Uuid) -> Result<(), MfaRepositoryError> {
+    let locked = sqlx::query!(
+        r#"
+    SELECT EXISTS(
+        SELECT 1 FROM totp_authenticators
+        WHERE user_id = $1 AND locked_until > $2
+    ) AS "locked!"
+            "#,
+        user_id,
+        Utc::now()
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch authenticator from database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?
+    .locked;
+
+    if locked {
+        Err(MfaRepositoryError::Locked)
+    } else {
+        Ok(())
+    }
+}
+
+/// Counts a wrong second factor against the user, across all of their logins, locking second
+/// factors out once there were too many. Every further failure extends the lockout, until a
+/// correct code resets the count.
+async fn record_failed_attempt(pool: &PgPool, user_id: Uuid) -> Result<(), MfaRepositoryError> {
+    sqlx::query!(
+        r#"
+    UPDATE totp_authenticators
+    SET failed_attempts = failed_attempts + 1,
+        locked_until = CASE WHEN failed_attempts + 1 >= $2 THEN $3 ELSE locked_until END
+    WHERE user_id = $1
+            "#,
+        user_id,
+        MAX_FAILED_ATTEMPTS,
+        Utc::now() + chrono::Duration::minutes(LOCKOUT_MINUTES)
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record failed attempt in database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+    Ok(())
+}
+
+/// Checks a second factor of the user, using it up: codes of the authenticator cannot be used
+/// again, and recovery codes are deleted.
+#[tracing::instrument(name = "Checking second factor", skip(pool, factor))]
+pub async fn use_second_factor(
+    pool: &PgPool,
+    user_id: Uuid,
+    factor: &SecondFactor,
+) -> Result<(), MfaRepositoryError> {
+    let now = Utc::now();
+    let authenticator = sqlx::query!(
+        r#"
+    SELECT secret, last_used_step, locked_until FROM totp_authenticators
+    WHERE user_id = $1 AND confirmed_at IS NOT NULL
+            "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => MfaRepositoryError::NotEnabled,
+        _ => {
+            tracing::error!("Failed to fetch authenticator from database: {:?}", e);
+            MfaRepositoryError::InternalError
+        }
+    })?;
+    if authenticator.locked_until.is_some_and(|until| until > now) {
+        return Err(MfaRepositoryError::Locked);
+    }
+
+    let used = match factor {
+        SecondFactor::Code(code) => {
+            match TotpSecret::from_bytes(authenticator.secret).verify(
+                code,
+                now,
+                authenticator.last_used_step,
+            ) {
+                // Checks the step again, in case the same code was used concurrently
+                Some(step) => sqlx::query!(
+                    r#"
+    UPDATE totp_authenticators SET last_used_step = $2
+    WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+                    user_id,
+                    step
+                )
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected()),
+                None => Ok(0),
+            }
+        }
+        SecondFactor::RecoveryCode(code) => sqlx::query!(
+            r#"
+    DELETE FROM recovery_codes
+    WHERE user_id = $1 AND code_hash = $2
+            "#,
+            user_id,
+            hash_recovery_code(code)
+        )
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()),
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to use second factor in database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+
+    if used == 0 {
+        record_failed_attempt(pool, user_id).await?;
+        return Err(MfaRepositoryError::InvalidCode);
+    }
+
+    sqlx::query!(
+        r#"
+    UPDATE totp_authenticators SET failed_attempts = 0, locked_until = NULL
+    WHERE user_id = $1
+            "#,
+        user_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to reset failed attempts in database: {:?}", e);
+        MfaRepositoryError::InternalError
+    })?;
+    Ok(())
+}
@@ -1 +1,9 @@
+pub(crate) mod batch_repository;
+pub(crate) mod checklist_repository;
+pub(crate) mod comment_repository;
+pub(crate) mod execution_repository;
+pub(crate) mod grant_repository;
+pub(crate) mod mfa_repository;
+pub(crate) mod share_link_repository;
+pub(crate) mod team_repository;
 pub(crate) mod user_repository;
@@ -0,0 +1,119 @@
+use crate::models::{
+    session_token::SessionToken,
+    share_link::{CreatedShareLink, ShareLink},
+};
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShareLinkRepositoryError {
+    #[error("share link not found")]
+    ShareLinkNotFound,
+    #[error("share link cannot expire in the past")]
+    InvalidExpiry,
+    #[error("internal error")]
+    InternalError,
+}
+
+/// Creates a link to the execution; only a hash of its token is stored.
+#[tracing::instrument(name = "Saving new share link in the database", skip(pool))]
+pub async fn insert_share_link(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<CreatedShareLink, ShareLinkRepositoryError> {
+    let created_at = Utc::now();
+    if expires_at.is_some_and(|expires_at| expires_at <= created_at) {
+        return Err(ShareLinkRepositoryError::InvalidExpiry);
+    }
+    let token = SessionToken::generate_new();
+
+    let link = sqlx::query!(
+        r#"
+    WITH link AS (
+        INSERT INTO share_links (id, execution_id, token_hash, created_by, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, created_by, created_at, expires_at
+    )
+    SELECT l.id, u.username, l.created_at, l.expires_at
+    FROM link l
+    JOIN users u ON u.id = l.created_by
+            "#,
+        Uuid::new_v4(),
+        execution_id,
+        token.to_hash(),
+        user_id,
+        created_at,
+        expires_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save share link in database: {:?}", e);
+        ShareLinkRepositoryError::InternalError
+    })?;
+
+    Ok(CreatedShareLink {
+        link: ShareLink {
+            id: link.id,
+            created_by: link.username,
+            created_at: link.created_at,
+            expires_at: link.expires_at,
+        },
+        token: token.to_secret_string().expose_secret().to_owned(),
+    })
+}
+
+#[tracing::instrument(name = "Fetching share links of an execution", skip(pool))]
+pub async fn list_share_links(
+    pool: &PgPool,
+    execution_id: Uuid,
+) -> Result<Vec<ShareLink>, ShareLinkRepositoryError> {
+    sqlx::query_as!(
+        ShareLink,
+        r#"
+    SELECT l.id, u.username AS created_by, l.created_at, l.expires_at
+    FROM share_links l
+    JOIN users u ON u.id = l.created_by
+    WHERE l.execution_id = $1
+    ORDER BY l.created_at
+        "#,
+        execution_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch share links from database: {:?}", e);
+        ShareLinkRepositoryError::InternalError
+    })
+}
+
+#[tracing::instrument(name = "Revoking a share link", skip(pool))]
+pub async fn revoke_share_link(
+    pool: &PgPool,
+    execution_id: Uuid,
+    link_id: Uuid,
+) -> Result<(), ShareLinkRepositoryError> {
+    let revoked = sqlx::query!(
+        r#"
+    DELETE FROM share_links
+    WHERE id = $1 AND execution_id = $2
+            "#,
+        link_id,
+        execution_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete share link from database: {:?}", e);
+        ShareLinkRepositoryError::InternalError
+    })?
+    .rows_affected();
+    if revoked == 0 {
+        return Err(ShareLinkRepositoryError::ShareLinkNotFound);
+    }
+    Ok(())
+}
@@ -0,0 +1,333 @@
+use crate::models::team::{Role, Team, TeamMember, TeamSummary};
+use chrono::Utc;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TeamRepositoryError {
+    #[error("team not found")]
+    TeamNotFound,
+    #[error("user '{username}' not found")]
+    UserNotFound { username: String },
+    #[error("user '{username}' is not a member of the team")]
+    MemberNotFound { username: String },
+    #[error("invalid team: {reason}")]
+    InvalidTeam { reason: String },
+    #[error("team must have at least one owner")]
+    LastOwner,
+    #[error("internal error")]
+    InternalError,
+}
+
+fn parse_role(role: &str) -> Result<Role, TeamRepositoryError> {
+    Role::from_str(role).map_err(|_| {
+        tracing::error!("Invalid team role in database: {}", role);
+        TeamRepositoryError::InternalError
+    })
+}
+
+#[tracing::instrument(name = "Saving new team in the database", skip(pool))]
+pub async fn insert_team(
+    pool: &PgPool,
+    user_id: Uuid,
+    name: &str,
+) -> Result<Team, TeamRepositoryError> {
+    if name.trim().is_empty() {
+        return Err(TeamRepositoryError::InvalidTeam {
+            reason: "name cannot be empty".to_owned(),
+        });
+    }
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?;
+
+    let team_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+    INSERT INTO teams (id, name, created_at)
+    VALUES ($1, $2, $3)
+            "#,
+        team_id,
+        name,
+        Utc::now()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create team in database: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?;
+    sqlx::query!(
+        r#"
+    INSERT INTO team_members (team_id, user_id, role)
+    VALUES ($1, $2, $3)
+            "#,
+        team_id,
+        user_id,
+        Role::Owner.as_str()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to add team member in database: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?;
+
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?;
+
+    get_team(pool, user_id, team_id).await
+}
+
+#[tracing::instrument(name = "Fetching teams of a user", skip(pool))]
+pub async fn list_teams(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<TeamSummary>, TeamRepositoryError> {
+    let rows = sqlx::query!(
+        r#"
+    SELECT t.id, t.name, m.role
+    FROM teams t
+    JOIN team_members m ON m.team_id = t.id
+    WHERE m.user_id = $1
+    ORDER BY t.created_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch teams from database: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(TeamSummary {
+                id: row.id,
+                name: row.name,
+                role: parse_role(&row.role)?,
+            })
+        })
+        .collect()
+}
+
+/// Fetches role of a user in a team, failing with `TeamNotFound` if they are not a member.
+#[tracing::instrument(name = "Fetching role of a user in a team", skip(pool))]
+pub async fn get_team_role(
+    pool: &PgPool,
+    user_id: Uuid,
+    team_id: Uuid,
+) -> Result<Role, TeamRepositoryError> {
+    let row = sqlx::query!(
+        r#"
+    SELECT role FROM team_members
+    WHERE team_id = $1 AND user_id = $2
+        "#,
+        team_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => TeamRepositoryError::TeamNotFound,
+        e => {
+            tracing::error!("Failed to fetch team role from database: {:?}", e);
+            TeamRepositoryError::InternalError
+        }
+    })?;
+    parse_role(&row.role)
+}
+
+/// Fetches a team with its members; only visible to members of the team.
+#[tracing::instrument(name = "Fetching a team", skip(pool))]
+pub async fn get_team(
+    pool: &PgPool,
+    user_id: Uuid,
+    team_id: Uuid,
+) -> Result<Team, TeamRepositoryError> {
+    get_team_role(pool, user_id, team_id).await?;
+    let team = sqlx::query!(
+        r#"
+    SELECT id, name FROM teams
+    WHERE id = $1
+        "#,
+        team_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => TeamRepositoryError::TeamNotFound,
+        e => {
+            tracing::error!("Failed to fetch team from database: {:?}", e);
+            TeamRepositoryError::InternalError
+        }
+    })?;
+    let members = sqlx::query!(
+        r#"
+    SELECT u.username, m.role
+    FROM team_members m
+    JOIN users u ON u.id = m.user_id
+    WHERE m.team_id = $1
+    ORDER BY u.username
+        "#,
+        team_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch team members from database: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?
+    .into_iter()
+    .map(|row| {
+        Ok(TeamMember {
+            username: row.username,
+            role: parse_role(&row.role)?,
+        })
+    })
+    .collect::<Result<_, TeamRepositoryError>>()?;
+
+    Ok(Team {
+        id: team.id,
+        name: team.name,
+        members,
+    })
+}
+
+/// Locks a team for changes of its members, so that checks for the last owner cannot race.
+async fn lock_team(
+    transaction: &mut Transaction<'_, Postgres>,
+    team_id: Uuid,
+) -> Result<(), TeamRepositoryError> {
+    sqlx::query!(
+        r#"
+    SELECT id FROM teams
+    WHERE id = $1
+    FOR UPDATE
+        "#,
+        team_id
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => TeamRepositoryError::TeamNotFound,
+        e => {
+            tracing::error!("Failed to fetch team from database: {:?}", e);
+            TeamRepositoryError::InternalError
+        }
+    })?;
+    Ok(())
+}
+
+/// Commits changes of members of a team, unless the team would be left without an owner.
+async fn commit_members(
+    mut transaction: Transaction<'_, Postgres>,
+    team_id: Uuid,
+) -> Result<(), TeamRepositoryError> {
+    let owners = sqlx::query!(
+        r#"
+    SELECT COUNT(*) AS "count!" FROM team_members
+    WHERE team_id = $1 AND role = $2
+        "#,
+        team_id,
+        Role::Owner.as_str()
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count team owners in database: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?
+    .count;
+    if owners == 0 {
+        return Err(TeamRepositoryError::LastOwner);
+    }
+
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        TeamRepositoryError::InternalError
+    })
+}
+
+/// Adds a user to a team with `role`, or changes their role if they already are a member.
+#[tracing::instrument(name = "Setting role of a team member", skip(pool))]
+pub async fn set_member_role(
+    pool: &PgPool,
+    team_id: Uuid,
+    username: &str,
+    role: Role,
+) -> Result<(), TeamRepositoryError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?;
+    lock_team(&mut transaction, team_id).await?;
+
+    let updated = sqlx::query!(
+        r#"
+    INSERT INTO team_members (team_id, user_id, role)
+    SELECT $1, id, $3 FROM users WHERE username = $2
+    ON CONFLICT (team_id, user_id) DO UPDATE SET role = EXCLUDED.role
+            "#,
+        team_id,
+        username,
+        role.as_str()
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to set team member role in database: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?
+    .rows_affected();
+    if updated == 0 {
+        return Err(TeamRepositoryError::UserNotFound {
+            username: username.to_owned(),
+        });
+    }
+
+    commit_members(transaction, team_id).await
+}
+
+#[tracing::instrument(name = "Removing a team member", skip(pool))]
+pub async fn remove_member(
+    pool: &PgPool,
+    team_id: Uuid,
+    username: &str,
+) -> Result<(), TeamRepositoryError> {
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?;
+    lock_team(&mut transaction, team_id).await?;
+
+    let removed = sqlx::query!(
+        r#"
+    DELETE FROM team_members m
+    USING users u
+    WHERE m.team_id = $1 AND m.user_id = u.id AND u.username = $2
+            "#,
+        team_id,
+        username
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to remove team member in database: {:?}", e);
+        TeamRepositoryError::InternalError
+    })?
+    .rows_affected();
+    if removed == 0 {
+        return Err(TeamRepositoryError::MemberNotFound {
+            username: username.to_owned(),
+        });
+    }
+
+    commit_members(transaction, team_id).await
+}
@@ -1,11 +1,18 @@
 use crate::{
-    models::{session_token::SessionToken, user::UserProfile},
+    configuration::SessionSettings,
+    controller::mfa_repository::{self, MfaRepositoryError},
+    models::{
+        mfa::SecondFactor,
+        session::{ClientInfo, LoginOutcome, Session, SessionTokens},
+        session_token::SessionToken,
+        user::UserProfile,
+    },
     routes::user::{CreateUserRequest, LoginUserRequest},
 };
 use argon2::{password_hash::SaltString, Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use chrono::Utc;
-use secrecy::ExposeSecret;
-use sqlx::PgPool;
+use secrecy::{ExposeSecret, SecretString};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 #[derive(Debug, thiserror::Error)]
@@ -18,24 +25,43 @@ pub enum UserRepositoryError {
     SessionNotFound,
     #[error("user with given id not found")]
     UserNotFound,
+    #[error("invalid or expired reset token")]
+    InvalidResetToken,
+    #[error("invalid or expired login challenge")]
+    InvalidMfaChallenge,
+    #[error("invalid authentication code")]
+    InvalidMfaCode,
+    #[error("too many failed authentication codes, try again later")]
+    MfaLocked,
     #[error("internal error")]
     InternalError,
 }
 
-#[tracing::instrument(name = "Saving new user in the database", skip(pool, user))]
-pub async fn insert_user(
-    pool: &PgPool,
-    user: &CreateUserRequest,
-) -> Result<(), UserRepositoryError> {
+/// Reset tokens can only be used for this long after they were requested.
+const PASSWORD_RESET_LIFETIME_MINUTES: i64 = 60;
+/// Second factor has to be provided this soon after the password.
+const MFA_CHALLENGE_LIFETIME_MINUTES: i64 = 5;
+/// Challenges are revoked after this many wrong codes, so that codes cannot be guessed.
+const MFA_CHALLENGE_MAX_ATTEMPTS: i32 = 5;
+
+fn hash_password(password: &SecretString) -> Result<String, UserRepositoryError> {
     let salt = SaltString::generate(&mut rand::thread_rng());
     let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(user.password.expose_secret().as_bytes(), &salt)
+    Ok(argon2
+        .hash_password(password.expose_secret().as_bytes(), &salt)
         .map_err(|e| {
             tracing::error!("Hashing error: {}", e);
             UserRepositoryError::InternalError
         })?
-        .to_string();
+        .to_string())
+}
+
+#[tracing::instrument(name = "Saving new user in the database", skip(pool, user))]
+pub async fn insert_user(
+    pool: &PgPool,
+    user: &CreateUserRequest,
+) -> Result<(), UserRepositoryError> {
+    let password_hash = hash_password(&user.password)?;
 
     sqlx::query!(
         r#"
@@ -85,14 +111,21 @@ async fn validate_password(
         }
     })?;
 
-    let parsed_hash = PasswordHash::new(&data.password).map_err(|e| {
+    verify_password(&data.password, &user.password)?;
+    Ok(data.id)
+}
+
+fn verify_password(
+    password_hash: &str,
+    password: &SecretString,
+) -> Result<(), UserRepositoryError> {
+    let parsed_hash = PasswordHash::new(password_hash).map_err(|e| {
         tracing::error!("Hashing error: {}", e);
         UserRepositoryError::InternalError
     })?;
 
-    match Argon2::default().verify_password(user.password.expose_secret().as_bytes(), &parsed_hash)
-    {
-        Ok(_) => Ok(data.id),
+    match Argon2::default().verify_password(password.expose_secret().as_bytes(), &parsed_hash) {
+        Ok(_) => Ok(()),
         Err(argon2::password_hash::Error::Password) => {
             Err(UserRepositoryError::InvalidUserOrPassword)
         }
@@ -103,18 +136,256 @@ async fn validate_password(
     }
 }
 
-#[tracing::instrument(name = "Creating session token", skip(pool, user_id))]
-async fn create_token(pool: &PgPool, user_id: &Uuid) -> Result<SessionToken, UserRepositoryError> {
-    let new_token = SessionToken::generate_new();
+/// Checks the password of a logged in user again, e.g. before they sign something, and returns
+/// their username.
+#[tracing::instrument(name = "Re-authenticating user", skip(pool, password))]
+pub async fn reauthenticate(
+    pool: &PgPool,
+    user_id: Uuid,
+    password: &SecretString,
+) -> Result<String, UserRepositoryError> {
+    let data = sqlx::query!(
+        r#"
+    SELECT username, password FROM users
+    WHERE id = $1
+            "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => UserRepositoryError::UserNotFound,
+        _ => {
+            tracing::error!("Failed to fetch user from database: {:?}", e);
+            UserRepositoryError::InternalError
+        }
+    })?;
+
+    verify_password(&data.password, password)?;
+    Ok(data.username)
+}
+
+/// Sets a new password hash and revokes everything obtained with the old password: sessions
+/// other than `kept_session_id`, outstanding reset tokens and pending login challenges.
+async fn replace_password(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    password_hash: &str,
+    kept_session_id: Option<Uuid>,
+) -> Result<(), UserRepositoryError> {
+    sqlx::query!(
+        r#"
+    UPDATE users SET password = $2
+    WHERE id = $1
+            "#,
+        user_id,
+        password_hash
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update password in database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?;
+
+    sqlx::query!(
+        r#"
+    DELETE FROM sessions
+    WHERE user_id = $1 AND ($2::UUID IS NULL OR id <> $2)
+            "#,
+        user_id,
+        kept_session_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete sessions from database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?;
+
+    sqlx::query!(
+        r#"
+    DELETE FROM password_resets
+    WHERE user_id = $1
+            "#,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!(
+            "Failed to delete password reset tokens from database: {:?}",
+            e
+        );
+        UserRepositoryError::InternalError
+    })?;
+
+    sqlx::query!(
+        r#"
+    DELETE FROM mfa_challenges
+    WHERE user_id = $1
+            "#,
+        user_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete login challenges from database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?;
+    Ok(())
+}
+
+/// Changes the password of a logged in user after checking their current one, and logs them out
+/// everywhere except for the current session.
+#[tracing::instrument(name = "Changing password", skip(pool, current_password, new_password))]
+pub async fn change_password(
+    pool: &PgPool,
+    user_id: Uuid,
+    session_id: Uuid,
+    current_password: &SecretString,
+    new_password: &SecretString,
+) -> Result<(), UserRepositoryError> {
+    reauthenticate(pool, user_id, current_password).await?;
+    let password_hash = hash_password(new_password)?;
+
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        UserRepositoryError::InternalError
+    })?;
+    replace_password(&mut transaction, user_id, &password_hash, Some(session_id)).await?;
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        UserRepositoryError::InternalError
+    })
+}
+
+/// Creates a reset token for the user, replacing tokens requested before. Returns no token for
+/// unknown users, so that callers can respond the same way regardless.
+#[tracing::instrument(name = "Creating password reset token", skip(pool))]
+pub async fn create_password_reset(
+    pool: &PgPool,
+    username: &str,
+) -> Result<Option<SessionToken>, UserRepositoryError> {
+    let token = SessionToken::generate_new();
+    let now = Utc::now();
+
+    let created = sqlx::query!(
+        r#"
+    WITH replaced AS (
+        DELETE FROM password_resets r
+        USING users u
+        WHERE r.user_id = u.id AND u.username = $2
+    )
+    INSERT INTO password_resets (token_hash, user_id, created_at, expires_at)
+    SELECT $1, id, $3, $4 FROM users
+    WHERE username = $2
+            "#,
+        token.to_database_value(),
+        username,
+        now,
+        now + chrono::Duration::minutes(PASSWORD_RESET_LIFETIME_MINUTES)
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create password reset token in database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?
+    .rows_affected();
+
+    Ok((created > 0).then_some(token))
+}
+
+/// Sets a new password using a reset token, which cannot be used again, and logs the user out
+/// everywhere.
+#[tracing::instrument(name = "Resetting password", skip(pool, token, new_password))]
+pub async fn reset_password(
+    pool: &PgPool,
+    token: SessionToken,
+    new_password: &SecretString,
+) -> Result<(), UserRepositoryError> {
+    let password_hash = hash_password(new_password)?;
+    let mut transaction = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {:?}", e);
+        UserRepositoryError::InternalError
+    })?;
+
+    let user_id = sqlx::query!(
+        r#"
+    DELETE FROM password_resets
+    WHERE token_hash = $1
+    RETURNING user_id, expires_at
+            "#,
+        token.to_database_value()
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => UserRepositoryError::InvalidResetToken,
+        _ => {
+            tracing::error!(
+                "Failed to fetch password reset token from database: {:?}",
+                e
+            );
+            UserRepositoryError::InternalError
+        }
+    })
+    .and_then(|row| {
+        if row.expires_at > Utc::now() {
+            Ok(row.user_id)
+        } else {
+            Err(UserRepositoryError::InvalidResetToken)
+        }
+    })?;
+
+    replace_password(&mut transaction, user_id, &password_hash, None).await?;
+
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {:?}", e);
+        UserRepositoryError::InternalError
+    })
+}
+
+/// Session the token of a request belongs to.
+#[derive(Debug)]
+pub struct ActiveSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+}
+
+#[tracing::instrument(name = "Creating session token", skip(pool, user_id, settings))]
+async fn create_token(
+    pool: &PgPool,
+    user_id: &Uuid,
+    client: &ClientInfo,
+    settings: &SessionSettings,
+) -> Result<SessionTokens, UserRepositoryError> {
+    let tokens = SessionTokens {
+        token: SessionToken::generate_new(),
+        refresh_token: SessionToken::generate_new(),
+    };
+    let now = Utc::now();
+    let expires_at = now + settings.absolute_lifetime();
 
     sqlx::query!(
         r#"
-    INSERT INTO sessions (token, user_id, valid_until)
-    VALUES ($1, $2, $3)
+    INSERT INTO sessions (
+        id, token, user_id, valid_until, expires_at, refresh_token, refresh_valid_until,
+        created_at, user_agent, ip
+    )
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             "#,
-        new_token.to_database_value().expose_secret().to_owned(),
+        Uuid::new_v4(),
+        tokens.token.to_database_value(),
         user_id,
-        Utc::now() + chrono::Duration::days(7)
+        expires_at.min(now + settings.idle_timeout()),
+        expires_at,
+        tokens.refresh_token.to_database_value(),
+        now + settings.refresh_lifetime(),
+        now,
+        client.user_agent,
+        client.ip
     )
     .execute(pool)
     .await
@@ -122,28 +393,205 @@ async fn create_token(pool: &PgPool, user_id: &Uuid) -> Result<SessionToken, Use
         tracing::error!("Failed to create token in database: {:?}", e);
         UserRepositoryError::InternalError
     })?;
-    Ok(new_token)
+    Ok(tokens)
 }
 
+/// Logs the user in, unless they have a second factor enrolled, in which case only a challenge
+/// to complete the login with is created. No challenges are created while the user's second
+/// factors are locked out after too many failed attempts.
 pub async fn login_user(
     pool: &PgPool,
     user: &LoginUserRequest,
-) -> Result<SessionToken, UserRepositoryError> {
+    client: &ClientInfo,
+    settings: &SessionSettings,
+) -> Result<LoginOutcome, UserRepositoryError> {
     let user_id = validate_password(pool, user).await?;
-    let token = create_token(pool, &user_id).await?;
-    Ok(token)
+    let mfa_enabled = mfa_repository::is_enabled(pool, user_id)
+        .await
+        .map_err(|_| UserRepositoryError::InternalError)?;
+    if !mfa_enabled {
+        let tokens = create_token(pool, &user_id, client, settings).await?;
+        return Ok(LoginOutcome::Authenticated(tokens));
+    }
+    mfa_repository::check_not_locked(pool, user_id)
+        .await
+        .map_err(|e| match e {
+            MfaRepositoryError::Locked => UserRepositoryError::MfaLocked,
+            _ => UserRepositoryError::InternalError,
+        })?;
+
+    let challenge = SessionToken::generate_new();
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::minutes(MFA_CHALLENGE_LIFETIME_MINUTES);
+    sqlx::query!(
+        r#"
+    INSERT INTO mfa_challenges (token_hash, user_id, created_at, expires_at)
+    VALUES ($1, $2, $3, $4)
+            "#,
+        challenge.to_database_value(),
+        user_id,
+        now,
+        expires_at
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create login challenge in database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?;
+    Ok(LoginOutcome::MfaRequired {
+        challenge,
+        expires_at,
+    })
 }
 
-pub async fn get_user_id_by_token(
+/// Completes a login started with the password by checking the second factor. The challenge can
+/// only be used once, and is revoked after too many wrong codes; wrong codes also count towards
+/// the lockout of the user's second factors.
+#[tracing::instrument(
+    name = "Completing login with second factor",
+    skip(pool, challenge, factor, settings)
+)]
+pub async fn complete_mfa_login(
+    pool: &PgPool,
+    challenge: SessionToken,
+    factor: &SecondFactor,
+    client: &ClientInfo,
+    settings: &SessionSettings,
+) -> Result<SessionTokens, UserRepositoryError> {
+    let user_id = sqlx::query!(
+        r#"
+    SELECT user_id FROM mfa_challenges
+    WHERE token_hash = $1 AND expires_at > $2 AND failed_attempts < $3
+            "#,
+        challenge.to_database_value(),
+        Utc::now(),
+        MFA_CHALLENGE_MAX_ATTEMPTS
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => UserRepositoryError::InvalidMfaChallenge,
+        _ => {
+            tracing::error!("Failed to fetch login challenge from database: {:?}", e);
+            UserRepositoryError::InternalError
+        }
+    })?
+    .user_id;
+
+    match mfa_repository::use_second_factor(pool, user_id, factor).await {
+        Ok(()) => {}
+        Err(MfaRepositoryError::InvalidCode) => {
+            sqlx::query!(
+                r#"
+    UPDATE mfa_challenges SET failed_attempts = failed_attempts + 1
+    WHERE token_hash = $1
+            "#,
+                challenge.to_database_value()
+            )
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to update login challenge in database: {:?}", e);
+                UserRepositoryError::InternalError
+            })?;
+            return Err(UserRepositoryError::InvalidMfaCode);
+        }
+        Err(MfaRepositoryError::NotEnabled) => {
+            return Err(UserRepositoryError::InvalidMfaChallenge)
+        }
+        Err(MfaRepositoryError::Locked) => return Err(UserRepositoryError::MfaLocked),
+        Err(_) => return Err(UserRepositoryError::InternalError),
+    }
+
+    // Deleting the challenge makes sure that only one login completes with it
+    let deleted = sqlx::query!(
+        r#"
+    DELETE FROM mfa_challenges
+    WHERE token_hash = $1
+            "#,
+        challenge.to_database_value()
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete login challenge from database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?
+    .rows_affected();
+    if deleted == 0 {
+        return Err(UserRepositoryError::InvalidMfaChallenge);
+    }
+
+    create_token(pool, &user_id, client, settings).await
+}
+
+/// Exchanges a refresh token for new tokens of the same session; the refresh token can only be
+/// used once, and the session still ends when the refresh token would have expired.
+#[tracing::instrument(name = "Refreshing session", skip(pool, refresh_token, settings))]
+pub async fn refresh_session(
+    pool: &PgPool,
+    refresh_token: SessionToken,
+    settings: &SessionSettings,
+) -> Result<SessionTokens, UserRepositoryError> {
+    let tokens = SessionTokens {
+        token: SessionToken::generate_new(),
+        refresh_token: SessionToken::generate_new(),
+    };
+    let now = Utc::now();
+    let expires_at = now + settings.absolute_lifetime();
+
+    sqlx::query!(
+        r#"
+    UPDATE sessions
+    SET token = $2, refresh_token = $3, last_used_at = $6,
+        valid_until = LEAST($4, refresh_valid_until),
+        expires_at = LEAST($5, refresh_valid_until)
+    WHERE refresh_token = $1 AND refresh_valid_until > $6
+    RETURNING id
+            "#,
+        refresh_token.to_database_value(),
+        tokens.token.to_database_value(),
+        tokens.refresh_token.to_database_value(),
+        expires_at.min(now + settings.idle_timeout()),
+        expires_at,
+        now
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => UserRepositoryError::SessionNotFound,
+        _ => {
+            tracing::error!("Failed to refresh session in database: {:?}", e);
+            UserRepositoryError::InternalError
+        }
+    })?;
+    Ok(tokens)
+}
+
+/// Finds the session of a token, recording that it was used and extending it by the idle
+/// timeout, up to its absolute expiry.
+pub async fn get_session_by_token(
     pg_pool: &PgPool,
     token: SessionToken,
-) -> Result<Uuid, UserRepositoryError> {
+    settings: &SessionSettings,
+) -> Result<ActiveSession, UserRepositoryError> {
+    let now = Utc::now();
     let result = sqlx::query!(
         r#"
-    SELECT user_id, valid_until from sessions 
+    UPDATE sessions
+    SET
+        last_used_at = CASE WHEN valid_until > $2 THEN $2 ELSE last_used_at END,
+        valid_until = CASE
+            WHEN valid_until > $2 THEN LEAST($3, expires_at)
+            ELSE valid_until
+        END
     WHERE token = $1
+    RETURNING id, user_id, valid_until, refresh_valid_until
             "#,
-        token.to_database_value().expose_secret().to_owned()
+        token.to_database_value(),
+        now,
+        now + settings.idle_timeout()
     )
     .fetch_one(pg_pool)
     .await
@@ -155,10 +603,19 @@ pub async fn get_user_id_by_token(
         }
     })?;
 
-    if result.valid_until > chrono::Utc::now() {
-        Ok(result.user_id)
+    if result.valid_until > now {
+        Ok(ActiveSession {
+            id: result.id,
+            user_id: result.user_id,
+        })
     } else {
-        let _ = delete_session_by_token(pg_pool, token).await;
+        // Sessions that can still be refreshed are kept.
+        if result
+            .refresh_valid_until
+            .map_or(true, |valid_until| valid_until <= now)
+        {
+            let _ = delete_session_by_token(pg_pool, token).await;
+        }
         Err(UserRepositoryError::SessionNotFound)
     }
 }
@@ -172,7 +629,7 @@ pub async fn delete_session_by_token(
     DELETE FROM sessions 
     WHERE token = $1
             "#,
-        token.to_database_value().expose_secret().to_owned()
+        token.to_database_value()
     )
     .execute(pg_pool)
     .await
@@ -183,6 +640,85 @@ pub async fn delete_session_by_token(
     Ok(())
 }
 
+/// Lists sessions of the user that have not expired or can still be refreshed, marking the
+/// current one.
+#[tracing::instrument(name = "Fetching sessions of a user", skip(pg_pool))]
+pub async fn list_sessions(
+    pg_pool: &PgPool,
+    user_id: Uuid,
+    current_session_id: Uuid,
+) -> Result<Vec<Session>, UserRepositoryError> {
+    sqlx::query_as!(
+        Session,
+        r#"
+    SELECT id, created_at, last_used_at, user_agent, ip, id = $2 AS "current!"
+    FROM sessions
+    WHERE user_id = $1 AND (valid_until > $3 OR refresh_valid_until > $3)
+    ORDER BY created_at
+        "#,
+        user_id,
+        current_session_id,
+        Utc::now()
+    )
+    .fetch_all(pg_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch sessions from database: {:?}", e);
+        UserRepositoryError::InternalError
+    })
+}
+
+#[tracing::instrument(name = "Deleting session of a user", skip(pg_pool))]
+pub async fn delete_session(
+    pg_pool: &PgPool,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<(), UserRepositoryError> {
+    let deleted = sqlx::query!(
+        r#"
+    DELETE FROM sessions
+    WHERE id = $1 AND user_id = $2
+            "#,
+        session_id,
+        user_id
+    )
+    .execute(pg_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete session from database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?
+    .rows_affected();
+    if deleted == 0 {
+        return Err(UserRepositoryError::SessionNotFound);
+    }
+    Ok(())
+}
+
+/// Logs the user out everywhere except for the given session.
+#[tracing::instrument(name = "Deleting other sessions of a user", skip(pg_pool))]
+pub async fn delete_other_sessions(
+    pg_pool: &PgPool,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<(), UserRepositoryError> {
+    sqlx::query!(
+        r#"
+    DELETE FROM sessions
+    WHERE user_id = $1 AND id <> $2
+            "#,
+        user_id,
+        session_id
+    )
+    .execute(pg_pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete sessions from database: {:?}", e);
+        UserRepositoryError::InternalError
+    })?;
+    Ok(())
+}
+
 pub async fn get_user_by_id(
     pg_pool: &PgPool,
     user_id: Uuid,
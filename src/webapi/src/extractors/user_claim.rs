@@ -8,6 +8,7 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
+    configuration::SessionSettings,
     controller::user_repository::{self, UserRepositoryError},
     models::session_token::SessionToken,
 };
@@ -15,6 +16,8 @@ use crate::{
 #[derive(Debug, Default, serde::Serialize)]
 pub(crate) struct UserClaim {
     pub user_id: Uuid,
+    /// Session the request was authorized with.
+    pub session_id: Uuid,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -56,6 +59,9 @@ impl FromRequest for UserClaim {
             .map(|h| h.to_owned());
 
         let pg_pool = req.app_data::<web::Data<PgPool>>().map(|p| p.to_owned());
+        let settings = req
+            .app_data::<web::Data<SessionSettings>>()
+            .map(|s| s.to_owned());
 
         Box::pin(async move {
             let token = header
@@ -75,15 +81,22 @@ impl FromRequest for UserClaim {
                 tracing::error!("Could not access database pool");
                 return Err(UserClaimError::InternalError);
             };
+            let Some(settings) = settings else {
+                tracing::error!("Could not access session settings");
+                return Err(UserClaimError::InternalError);
+            };
 
-            let user_id = user_repository::get_user_id_by_token(&pg_pool, token)
+            let session = user_repository::get_session_by_token(&pg_pool, token, &settings)
                 .await
                 .map_err(|e| match e {
                     UserRepositoryError::SessionNotFound => UserClaimError::Unauthorized,
                     _ => UserClaimError::InternalError,
                 })?;
 
-            Ok(UserClaim { user_id })
+            Ok(UserClaim {
+                user_id: session.user_id,
+                session_id: session.id,
+            })
         })
     }
 }
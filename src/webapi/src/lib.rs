@@ -2,6 +2,7 @@ pub mod configuration;
 pub mod controller;
 pub mod extractors;
 pub mod models;
+pub mod notifier;
 pub mod routes;
 pub mod startup;
 pub mod telemetry;
@@ -1,7 +1,7 @@
 use secrecy::ExposeSecret;
 use sqlx::postgres::PgPool;
 use std::net::TcpListener;
-use webapi::{configuration, startup, telemetry};
+use webapi::{configuration, notifier, startup, telemetry};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -21,5 +21,6 @@ async fn main() -> std::io::Result<()> {
         configuration.application.host, configuration.application.port
     );
     let listener = TcpListener::bind(address)?;
-    startup::run(listener, connection_pool)?.await
+    let notifier = notifier::get_notifier(&configuration.notifier);
+    startup::run(listener, connection_pool, configuration.session, notifier)?.await
 }
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::execution::{Execution, Progress, State};
+
+/// Number of executions listed as lagging behind in a batch.
+const LAGGING_LIMIT: usize = 10;
+
+/// Execution of a batch that is behind the others.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LaggingExecution {
+    pub id: Uuid,
+    pub target: String,
+    pub state: State,
+    pub progress: Progress,
+}
+
+/// Executions of one checklist against many targets with their progress rolled up.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Batch {
+    pub id: Uuid,
+    pub checklist_id: Uuid,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    /// Number of executions, one per target.
+    pub executions: usize,
+    /// Required leaf tasks of all executions and how many of them are complete.
+    pub progress: Progress,
+    /// Number of executions in each state.
+    pub states: BTreeMap<State, usize>,
+    /// Blocked and failed executions, followed by unfinished ones behind the average progress.
+    pub lagging: Vec<LaggingExecution>,
+}
+
+fn completion(progress: &Progress) -> f64 {
+    if progress.total == 0 {
+        1.0
+    } else {
+        progress.completed as f64 / progress.total as f64
+    }
+}
+
+impl Batch {
+    pub fn new(
+        id: Uuid,
+        checklist_id: Uuid,
+        title: String,
+        created_at: DateTime<Utc>,
+        executions: &[Execution],
+    ) -> Self {
+        let mut progress = Progress::default();
+        let mut states = BTreeMap::new();
+        for execution in executions.iter() {
+            progress.completed += execution.progress.completed;
+            progress.total += execution.progress.total;
+            *states.entry(execution.state).or_insert(0) += 1;
+        }
+
+        let average = executions
+            .iter()
+            .map(|e| completion(&e.progress))
+            .sum::<f64>()
+            / executions.len().max(1) as f64;
+        let mut lagging: Vec<&Execution> = executions
+            .iter()
+            .filter(|e| match e.state {
+                State::Blocked | State::Failed => true,
+                state => !state.is_finished() && completion(&e.progress) < average,
+            })
+            .collect();
+        let rank = |e: &Execution| match e.state {
+            State::Blocked | State::Failed => 0,
+            _ => 1,
+        };
+        lagging.sort_by(|a, b| {
+            rank(a)
+                .cmp(&rank(b))
+                .then(completion(&a.progress).total_cmp(&completion(&b.progress)))
+        });
+
+        Self {
+            id,
+            checklist_id,
+            title,
+            created_at,
+            executions: executions.len(),
+            progress,
+            states,
+            lagging: lagging
+                .into_iter()
+                .take(LAGGING_LIMIT)
+                .map(|e| LaggingExecution {
+                    id: e.id,
+                    target: e.target.clone().unwrap_or_default(),
+                    state: e.state,
+                    progress: e.progress,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::comment::CommentCounts;
+
+    fn execution(target: &str, state: State, completed: usize) -> Execution {
+        Execution {
+            id: Uuid::new_v4(),
+            checklist_id: Uuid::new_v4(),
+            checklist_version: 1,
+            title: "Test".to_owned(),
+            variables: BTreeMap::new(),
+            batch_id: None,
+            target: Some(target.to_owned()),
+            team_id: None,
+            task_statuses: Vec::new(),
+            state,
+            reason: None,
+            progress: Progress {
+                completed,
+                total: 4,
+            },
+            comments: CommentCounts::default(),
+        }
+    }
+
+    #[test]
+    fn test_batch_rolls_up_executions() {
+        let executions = [
+            execution("store-1", State::Done, 4),
+            execution("store-2", State::InProgress, 3),
+            execution("store-3", State::InProgress, 1),
+            execution("store-4", State::Blocked, 2),
+            execution("store-5", State::NotStarted, 0),
+        ];
+
+        let batch = Batch::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "Opening".to_owned(),
+            Utc::now(),
+            &executions,
+        );
+
+        assert_eq!(5, batch.executions);
+        assert_eq!(
+            Progress {
+                completed: 10,
+                total: 20
+            },
+            batch.progress
+        );
+        assert_eq!(Some(&2), batch.states.get(&State::InProgress));
+        assert_eq!(None, batch.states.get(&State::Failed));
+        let lagging: Vec<&str> = batch.lagging.iter().map(|e| e.target.as_str()).collect();
+        assert_eq!(vec!["store-4", "store-5", "store-3"], lagging);
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Kind of answer a task requires to be completed.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskInput {
+    Text,
+    Number {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unit: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    Boolean,
+    /// Calendar date in `YYYY-MM-DD` format.
+    Date,
+    Choice {
+        options: Vec<String>,
+        #[serde(default)]
+        multiple: bool,
+    },
+}
+
+impl TaskInput {
+    /// Checks that the definition itself makes sense, e.g. that a choice has options.
+    pub fn validate_definition(&self) -> Result<(), String> {
+        match self {
+            Self::Number {
+                min: Some(min),
+                max: Some(max),
+                ..
+            } if min > max => Err("minimum cannot be greater than maximum".to_owned()),
+            Self::Choice { options, .. } => {
+                if options.is_empty() {
+                    return Err("choice needs at least one option".to_owned());
+                }
+                let mut seen = std::collections::HashSet::new();
+                match options.iter().find(|o| !seen.insert(o.as_str())) {
+                    Some(option) => Err(format!("duplicate option '{option}'")),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that `value` is a valid answer.
+    pub fn validate_value(&self, value: &Value) -> Result<(), String> {
+        match (self, value) {
+            (Self::Text, Value::String(text)) if !text.trim().is_empty() => Ok(()),
+            (Self::Text, _) => Err("expected a non-empty text".to_owned()),
+            (Self::Number { min, max, .. }, Value::Number(number)) => {
+                let number = number.as_f64().unwrap_or(f64::NAN);
+                if min.is_some_and(|min| number < min) {
+                    Err(format!("value cannot be lower than {}", min.unwrap()))
+                } else if max.is_some_and(|max| number > max) {
+                    Err(format!("value cannot be greater than {}", max.unwrap()))
+                } else {
+                    Ok(())
+                }
+            }
+            (Self::Number { .. }, _) => Err("expected a number".to_owned()),
+            (Self::Boolean, Value::Bool(_)) => Ok(()),
+            (Self::Boolean, _) => Err("expected a boolean".to_owned()),
+            (Self::Date, Value::String(date))
+                if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok() =>
+            {
+                Ok(())
+            }
+            (Self::Date, _) => Err("expected a date in YYYY-MM-DD format".to_owned()),
+            (
+                Self::Choice {
+                    options,
+                    multiple: false,
+                },
+                Value::String(choice),
+            ) => {
+                if options.contains(choice) {
+                    Ok(())
+                } else {
+                    Err(format!("'{choice}' is not one of the options"))
+                }
+            }
+            (
+                Self::Choice {
+                    options,
+                    multiple: true,
+                },
+                Value::Array(choices),
+            ) => {
+                let mut seen = std::collections::HashSet::new();
+                for choice in choices {
+                    match choice.as_str() {
+                        Some(choice) if !options.iter().any(|o| o == choice) => {
+                            return Err(format!("'{choice}' is not one of the options"))
+                        }
+                        Some(choice) if !seen.insert(choice) => {
+                            return Err(format!("'{choice}' is chosen more than once"))
+                        }
+                        Some(_) => {}
+                        None => return Err("expected a list of options".to_owned()),
+                    }
+                }
+                Ok(())
+            }
+            (
+                Self::Choice {
+                    multiple: false, ..
+                },
+                _,
+            ) => Err("expected one of the options".to_owned()),
+            (Self::Choice { multiple: true, .. }, _) => {
+                Err("expected a list of options".to_owned())
+            }
+        }
+    }
+}
+
+/// Condition on the answer given to an earlier task.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Condition {
+    pub task_id: Uuid,
+    /// Expected answer; for a multiple choice it is enough for the option to be chosen.
+    pub equals: Value,
+}
+
+impl Condition {
+    pub fn holds(&self, values: &HashMap<Uuid, Value>) -> bool {
+        match values.get(&self.task_id) {
+            Some(Value::Array(chosen)) if !self.equals.is_array() => chosen.contains(&self.equals),
+            Some(value) => *value == self.equals,
+            None => false,
+        }
+    }
+
+    /// Checks that the expected answer is a valid value of the input of the referenced task.
+    pub fn validate(&self, input: &TaskInput) -> Result<(), String> {
+        match input {
+            TaskInput::Choice { multiple: true, .. } if !self.equals.is_array() => {
+                input.validate_value(&Value::Array(vec![self.equals.clone()]))
+            }
+            _ => input.validate_value(&self.equals),
+        }
+    }
+}
+
+/// Reference to a checklist whose tasks are used as sub-tasks of another checklist's task.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Include {
+    pub checklist_id: Uuid,
+    /// Version used by an execution; templates always include the current version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Task {
+    /// Identifier that stays the same when the task is edited or moved within the checklist.
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+    pub title: String,
+    /// Optional tasks do not have to be completed for the execution to be done.
+    #[serde(default)]
+    pub optional: bool,
+    /// Answer that has to be given to complete the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<TaskInput>,
+    /// The task is shown only when the condition holds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible_if: Option<Condition>,
+    /// The task is required only when the condition holds, regardless of `optional`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_if: Option<Condition>,
+    /// Earlier tasks that have to be complete before this one can be done.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<Uuid>,
+    /// Sub-tasks have to be completed in order.
+    #[serde(default)]
+    pub sequential: bool,
+    /// Name of a list; sub-tasks are repeated for each of its items when an execution is started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_for: Option<String>,
+    /// Checklist whose tasks become sub-tasks of this one when an execution is started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Include>,
+    /// A second user has to verify the task after it is done.
+    #[serde(default)]
+    pub requires_verification: bool,
+    /// Changing the state of the task has to be signed by re-entering the user's password.
+    #[serde(default)]
+    pub requires_signature: bool,
+    /// Sub-tasks; a task that has them acts as a section.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Task>,
+}
+
+impl Task {
+    /// Ids of checklists included by `tasks` or their sub-tasks.
+    pub fn included_checklists(tasks: &[Task]) -> Vec<Uuid> {
+        let mut ids = Vec::new();
+        for task in tasks {
+            ids.extend(task.include.as_ref().map(|include| include.checklist_id));
+            ids.extend(Task::included_checklists(&task.children));
+        }
+        ids
+    }
+
+    /// Derives new ids of the task and its sub-tasks from `namespace`, along with references
+    /// to them, so that a checklist can be included more than once.
+    fn remap_ids(&mut self, namespace: &Uuid) {
+        let remap = |id: &Uuid| Uuid::new_v5(namespace, id.as_bytes());
+        self.id = remap(&self.id);
+        for condition in [&mut self.visible_if, &mut self.required_if]
+            .into_iter()
+            .flatten()
+        {
+            condition.task_id = remap(&condition.task_id);
+        }
+        for dependency in self.depends_on.iter_mut() {
+            *dependency = remap(dependency);
+        }
+        for child in self.children.iter_mut() {
+            child.remap_ids(namespace);
+        }
+    }
+
+    fn render(&self, values: &BTreeMap<String, String>) -> Task {
+        Task {
+            title: render(&self.title, values),
+            children: self.children.iter().map(|t| t.render(values)).collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Value given when a checklist is executed, used in place of `{{name}}` in its title and tasks.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Variable {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Lists take a number of values, each used by one instance of the sections repeated over it.
+    #[serde(default)]
+    pub list: bool,
+}
+
+/// Value of a variable given when a checklist is executed.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum VariableValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl VariableValue {
+    fn is_valid_for(&self, variable: &Variable) -> bool {
+        match self {
+            Self::Text(text) => !variable.list && !text.trim().is_empty(),
+            Self::List(items) => {
+                variable.list && !items.is_empty() && items.iter().all(|i| !i.trim().is_empty())
+            }
+        }
+    }
+}
+
+/// Names of the placeholders in `text`, e.g. `hostname` for `Restart {{hostname}}`.
+pub fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        names.push(rest[start + 2..start + end].trim());
+        rest = &rest[start + end + 2..];
+    }
+    names
+}
+
+/// Replaces placeholders in `text` with their values; ones without a value are left as they are.
+pub fn render(text: &str, values: &BTreeMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + end + 2];
+        rendered.push_str(&rest[..start]);
+        match values.get(placeholder[2..placeholder.len() - 2].trim()) {
+            Some(value) => rendered.push_str(value),
+            None => rendered.push_str(placeholder),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Checklist {
+    pub id: Uuid,
+    pub version: i32,
+    pub title: String,
+    #[serde(default)]
+    pub variables: Vec<Variable>,
+    pub tasks: Vec<Task>,
+    pub owner: String,
+    /// Team the checklist belongs to, if it is shared with one.
+    pub team_id: Option<Uuid>,
+}
+
+/// Replaces includes in `tasks` with tasks of the included checklists and merges their
+/// variables into `variables`; `included` holds checklists being expanded to detect cycles.
+fn expand_includes(
+    tasks: &mut [Task],
+    checklists: &HashMap<Uuid, Checklist>,
+    variables: &mut Vec<Variable>,
+    included: &mut Vec<Uuid>,
+) -> Result<(), String> {
+    for task in tasks.iter_mut() {
+        let Some(include) = &mut task.include else {
+            expand_includes(&mut task.children, checklists, variables, included)?;
+            continue;
+        };
+        let checklist = checklists
+            .get(&include.checklist_id)
+            .ok_or_else(|| format!("included checklist {} not found", include.checklist_id))?;
+        if included.contains(&checklist.id) {
+            return Err(format!("checklist {} includes itself", checklist.id));
+        }
+        include.version = Some(checklist.version);
+
+        let mut children = checklist.tasks.clone();
+        for child in children.iter_mut() {
+            child.remap_ids(&task.id);
+        }
+        included.push(checklist.id);
+        expand_includes(&mut children, checklists, variables, included)?;
+        included.pop();
+        task.children = children;
+
+        for variable in checklist.variables.iter() {
+            if !variables.iter().any(|v| v.name == variable.name) {
+                variables.push(variable.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Checklist {
+    /// Replaces includes with tasks of the current versions of the included checklists,
+    /// given by their ids in `checklists`.
+    pub fn resolve_includes(
+        mut self,
+        checklists: &HashMap<Uuid, Checklist>,
+    ) -> Result<Self, String> {
+        let mut included = vec![self.id];
+        expand_includes(
+            &mut self.tasks,
+            checklists,
+            &mut self.variables,
+            &mut included,
+        )?;
+        Ok(self)
+    }
+
+    /// Returns the title and tasks with placeholders replaced by `values`, which have to be given
+    /// for exactly the declared variables.
+    ///
+    /// Placeholders of lists are left in place to be replaced by items of repeated sections.
+    pub fn render(
+        &self,
+        values: &BTreeMap<String, VariableValue>,
+    ) -> Result<(String, Vec<Task>), String> {
+        let missing: Vec<&str> = self
+            .variables
+            .iter()
+            .filter(|v| {
+                values
+                    .get(&v.name)
+                    .map_or(true, |value| !value.is_valid_for(v))
+            })
+            .map(|v| v.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "missing or invalid values of {}",
+                missing.join(", ")
+            ));
+        }
+        if let Some(name) = values
+            .keys()
+            .find(|name| !self.variables.iter().any(|v| v.name == **name))
+        {
+            return Err(format!("unknown variable {name}"));
+        }
+        let values: BTreeMap<String, String> = values
+            .iter()
+            .filter_map(|(name, value)| match value {
+                VariableValue::Text(text) => Some((name.clone(), text.clone())),
+                VariableValue::List(_) => None,
+            })
+            .collect();
+        let values = &values;
+        Ok((
+            render(&self.title, values),
+            self.tasks.iter().map(|t| t.render(values)).collect(),
+        ))
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChecklistSummary {
+    pub id: Uuid,
+    pub title: String,
+    pub team_id: Option<Uuid>,
+    /// Whether the checklist was created by another user and shared with the caller, through
+    /// a team or a grant.
+    pub shared: bool,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChecklistVersionSummary {
+    pub version: i32,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn input(definition: Value) -> TaskInput {
+        serde_json::from_value(definition).unwrap()
+    }
+
+    fn checklist(title: &str, variables: Value, tasks: Value) -> Checklist {
+        Checklist {
+            id: Uuid::new_v4(),
+            version: 1,
+            title: title.to_owned(),
+            variables: serde_json::from_value(variables).unwrap(),
+            tasks: serde_json::from_value(tasks).unwrap(),
+            owner: "jozin".to_owned(),
+            team_id: None,
+        }
+    }
+
+    #[test]
+    fn test_resolving_includes() {
+        let briefing = checklist(
+            "Safety briefing",
+            json!([{"name": "site"}]),
+            json!([
+                {"id": "9d2f7a35-1b7c-4f3e-9a4e-61c2f0d4e001", "title": "Exits at {{site}}"},
+                {"title": "Extinguishers",
+                    "depends_on": ["9d2f7a35-1b7c-4f3e-9a4e-61c2f0d4e001"]}
+            ]),
+        );
+        let procedure = checklist(
+            "Procedure",
+            json!([]),
+            json!([
+                {"title": "Morning briefing", "include": {"checklist_id": briefing.id}},
+                {"title": "Evening briefing", "include": {"checklist_id": briefing.id}}
+            ]),
+        );
+        let checklists = HashMap::from([(briefing.id, briefing)]);
+
+        let resolved = procedure.resolve_includes(&checklists).unwrap();
+
+        let (morning, evening) = (&resolved.tasks[0], &resolved.tasks[1]);
+        assert_eq!(Some(1), morning.include.as_ref().unwrap().version);
+        assert_eq!("Exits at {{site}}", morning.children[0].title);
+        assert_ne!(morning.children[0].id, evening.children[0].id);
+        assert_eq!(vec![morning.children[0].id], morning.children[1].depends_on);
+        assert_eq!("site", resolved.variables[0].name);
+    }
+
+    #[test]
+    fn test_resolving_cyclic_includes_fails() {
+        let mut first = checklist("First", json!([]), json!([]));
+        let second = checklist(
+            "Second",
+            json!([]),
+            json!([{"title": "First", "include": {"checklist_id": first.id}}]),
+        );
+        first.tasks = serde_json::from_value(
+            json!([{"title": "Second", "include": {"checklist_id": second.id}}]),
+        )
+        .unwrap();
+        let checklists = HashMap::from([(second.id, second)]);
+
+        assert!(first.resolve_includes(&checklists).is_err());
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            vec!["hostname", "release_version"],
+            placeholders("Deploy {{ hostname }} at {{release_version}} {{unclosed")
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let values = BTreeMap::from([("hostname".to_owned(), "db-1".to_owned())]);
+
+        assert_eq!(
+            "Restart db-1, not {{other}}",
+            render("Restart {{hostname}}, not {{other}}", &values)
+        );
+    }
+
+    #[test]
+    fn test_condition_holds() {
+        let water = Uuid::new_v4();
+        let findings = Uuid::new_v4();
+        let values = HashMap::from([(water, json!(true)), (findings, json!(["rust", "dent"]))]);
+        let condition = |task_id, equals| Condition { task_id, equals };
+
+        assert!(condition(water, json!(true)).holds(&values));
+        assert!(!condition(water, json!(false)).holds(&values));
+        assert!(condition(findings, json!("dent")).holds(&values));
+        assert!(!condition(findings, json!("crack")).holds(&values));
+        assert!(!condition(Uuid::new_v4(), json!(true)).holds(&values));
+    }
+
+    #[test]
+    fn test_invalid_input_definitions() {
+        for definition in [
+            json!({"type": "number", "min": 10, "max": 1}),
+            json!({"type": "choice", "options": []}),
+            json!({"type": "choice", "options": ["a", "a"]}),
+        ] {
+            assert!(input(definition).validate_definition().is_err());
+        }
+    }
+
+    #[test]
+    fn test_valid_values() {
+        for (definition, value) in [
+            (json!({"type": "text"}), json!("batch 42")),
+            (
+                json!({"type": "number", "unit": "psi", "min": 30, "max": 40}),
+                json!(32.5),
+            ),
+            (json!({"type": "boolean"}), json!(false)),
+            (json!({"type": "date"}), json!("2024-02-29")),
+            (
+                json!({"type": "choice", "options": ["ok", "nok"]}),
+                json!("ok"),
+            ),
+            (
+                json!({"type": "choice", "options": ["a", "b", "c"], "multiple": true}),
+                json!(["a", "c"]),
+            ),
+        ] {
+            assert_eq!(Ok(()), input(definition).validate_value(&value));
+        }
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        for (definition, value) in [
+            (json!({"type": "text"}), json!("")),
+            (json!({"type": "text"}), json!(1)),
+            (json!({"type": "number", "min": 30}), json!(29)),
+            (json!({"type": "number", "max": 40}), json!(41)),
+            (json!({"type": "number"}), json!("12")),
+            (json!({"type": "boolean"}), json!("yes")),
+            (json!({"type": "date"}), json!("2023-02-29")),
+            (json!({"type": "choice", "options": ["ok"]}), json!("maybe")),
+            (json!({"type": "choice", "options": ["ok"]}), json!(["ok"])),
+            (
+                json!({"type": "choice", "options": ["a"], "multiple": true}),
+                json!(["a", "a"]),
+            ),
+        ] {
+            assert!(input(definition).validate_value(&value).is_err());
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Comment {
+    pub id: Uuid,
+    /// Task the comment is about; comments without one are about the whole execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<Uuid>,
+    /// Username of the author.
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Number of comments on an execution, in total and on each of its tasks.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CommentCounts {
+    pub total: i64,
+    /// Tasks without comments are left out.
+    #[serde(default)]
+    pub tasks: BTreeMap<Uuid, i64>,
+}
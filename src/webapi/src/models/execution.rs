@@ -0,0 +1,1671 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::{
+    checklist::{render, Condition, Include, Task, TaskInput, VariableValue},
+    comment::CommentCounts,
+};
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    NotStarted,
+    InProgress,
+    Skipped,
+    Blocked,
+    Failed,
+    Done,
+}
+
+impl State {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotStarted => "not_started",
+            Self::InProgress => "in_progress",
+            Self::Skipped => "skipped",
+            Self::Blocked => "blocked",
+            Self::Failed => "failed",
+            Self::Done => "done",
+        }
+    }
+
+    /// Whether the state counts as completed when checking if an execution can be done.
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Done | Self::Skipped)
+    }
+
+    /// Whether an execution in this state no longer accepts changes to its tasks.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Done | Self::Skipped | Self::Failed)
+    }
+
+    pub fn can_transition_to(&self, next: State) -> bool {
+        use State::*;
+        match (*self, next) {
+            (current, next) if current == next => true,
+            (NotStarted | InProgress, _) => true,
+            (Blocked, NotStarted | InProgress | Skipped | Failed) => true,
+            (Failed | Skipped | Done, NotStarted | InProgress) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[error("invalid state")]
+pub struct InvalidState;
+
+impl FromStr for State {
+    type Err = InvalidState;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "not_started" => Ok(Self::NotStarted),
+            "in_progress" => Ok(Self::InProgress),
+            "skipped" => Ok(Self::Skipped),
+            "blocked" => Ok(Self::Blocked),
+            "failed" => Ok(Self::Failed),
+            "done" => Ok(Self::Done),
+            _ => Err(InvalidState),
+        }
+    }
+}
+
+/// Reasons for rejecting a change of state of an execution or one of its tasks.
+#[derive(Debug, PartialEq, serde::Serialize, thiserror::Error)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum TransitionError {
+    #[error("task not found")]
+    TaskNotFound,
+    #[error("task is hidden by its conditions")]
+    TaskHidden,
+    #[error("cannot change state from '{from}' to '{to}'")]
+    InvalidTransition { from: State, to: State },
+    #[error("a reason is required to skip")]
+    ReasonRequired,
+    #[error("execution is already {state}")]
+    ExecutionFinished { state: State },
+    #[error("state of a task with sub-tasks is derived from its children")]
+    DerivedState,
+    #[error("required tasks are not complete")]
+    IncompleteTasks { tasks: Vec<TaskSummary> },
+    #[error("tasks that have to be completed first are not complete")]
+    BlockedByTasks { tasks: Vec<TaskSummary> },
+    #[error("a value is required to complete the task")]
+    ValueRequired,
+    #[error("invalid value: {reason}")]
+    InvalidValue { reason: String },
+    #[error("task is assigned to '{assignee}'")]
+    NotAssignee { assignee: String },
+    #[error("task does not require verification")]
+    VerificationNotRequired,
+    #[error("task has to be done before it is verified")]
+    NotPerformed,
+    #[error("task has to be verified by a different user than the one who performed it")]
+    SelfVerification,
+    #[error("task is already verified")]
+    AlreadyVerified,
+    #[error("tasks are not verified")]
+    UnverifiedTasks { tasks: Vec<TaskSummary> },
+    #[error("a signature is required to change state of the task")]
+    SignatureRequired,
+}
+
+/// Reference to a task of an execution, either by its id or by its position.
+///
+/// Positions are kept only for compatibility with clients that predate task ids.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub enum TaskRef {
+    Id(Uuid),
+    Index(usize),
+}
+
+impl FromStr for TaskRef {
+    type Err = InvalidTaskRef;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = Uuid::parse_str(s) {
+            Ok(Self::Id(id))
+        } else {
+            s.parse().map(Self::Index).map_err(|_| InvalidTaskRef)
+        }
+    }
+}
+
+impl TryFrom<String> for TaskRef {
+    type Error = InvalidTaskRef;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::from_str(&s)
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[error("invalid task reference")]
+pub struct InvalidTaskRef;
+
+/// Identifies a task in error responses.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TaskSummary {
+    pub id: Uuid,
+    pub title: String,
+}
+
+/// Change of a task requested by a user.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TaskUpdate {
+    pub state: State,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Answer to the task's input, replacing any previously captured one.
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+/// What the signer attests to by signing a task.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureMeaning {
+    Performed,
+    Reviewed,
+    Approved,
+}
+
+/// Electronic signature of a change of state, made by re-entering the user's password.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Signature {
+    /// Username of the signer at the moment of signing.
+    pub username: String,
+    pub meaning: SignatureMeaning,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Assignment of a task to a user, replacing the previous one.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Assignment {
+    /// Username of the assignee; the task is unassigned without one.
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+/// Open task assigned to a user, together with the execution it is part of.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AssignedTask {
+    pub execution_id: Uuid,
+    pub execution_title: String,
+    pub id: Uuid,
+    pub title: String,
+    pub state: State,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+/// Number of required leaf tasks of an execution and how many of them are complete.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    fn of(tasks: &[TaskStatus]) -> Self {
+        let mut progress = Progress::default();
+        for task in tasks.iter() {
+            task.for_each_required_leaf(&mut |t| {
+                progress.total += 1;
+                if t.state.is_complete() {
+                    progress.completed += 1;
+                }
+            });
+        }
+        progress
+    }
+}
+
+/// Snapshot of a template task together with its progress within an execution.
+///
+/// The state of a task with sub-tasks is derived from the states of its children.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TaskStatus {
+    pub id: Uuid,
+    pub title: String,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible_if: Option<Condition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_if: Option<Condition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<Uuid>,
+    #[serde(default)]
+    pub sequential: bool,
+    /// List the task's sub-tasks are repeated for; each of them is an instance for one item.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_for: Option<String>,
+    /// Item of the list an instance of a repeated section is for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    /// Progress of an instance of a repeated section.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+    /// Checklist, and its version, whose tasks were included as sub-tasks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Include>,
+    /// Whether the task and all its parents are shown, given the answers captured so far.
+    #[serde(default)]
+    pub visible: bool,
+    /// Whether the task has to be complete, given the answers captured so far.
+    #[serde(default)]
+    pub required: bool,
+    pub state: State,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<TaskInput>,
+    /// Answer captured for the task's input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// Username of the user responsible for the task and its sub-tasks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub requires_verification: bool,
+    /// Username of the user who last changed the state of the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub performed_by: Option<String>,
+    /// Username of the user who verified the task, if it requires verification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_by: Option<String>,
+    #[serde(default)]
+    pub requires_signature: bool,
+    /// Signature of the last change of state, if the task requires one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Signature>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TaskStatus>,
+}
+
+impl From<&Task> for TaskStatus {
+    fn from(task: &Task) -> Self {
+        Self {
+            id: task.id,
+            title: task.title.clone(),
+            optional: task.optional,
+            visible_if: task.visible_if.clone(),
+            required_if: task.required_if.clone(),
+            depends_on: task.depends_on.clone(),
+            sequential: task.sequential,
+            repeat_for: task.repeat_for.clone(),
+            item: None,
+            progress: None,
+            include: task.include.clone(),
+            visible: true,
+            required: !task.optional,
+            state: State::NotStarted,
+            reason: None,
+            input: task.input.clone(),
+            value: None,
+            assignee: None,
+            due_at: None,
+            requires_verification: task.requires_verification,
+            performed_by: None,
+            verified_by: None,
+            requires_signature: task.requires_signature,
+            signature: None,
+            children: task.children.iter().map(TaskStatus::from).collect(),
+        }
+    }
+}
+
+impl TaskStatus {
+    /// Creates statuses of `tasks`, with an instance of each repeated section for every item of
+    /// its list in `values`.
+    pub fn from_tasks(tasks: &[Task], values: &BTreeMap<String, VariableValue>) -> Vec<Self> {
+        let mut statuses: Vec<TaskStatus> = tasks.iter().map(TaskStatus::from).collect();
+        for status in statuses.iter_mut() {
+            status.expand_repeats(values);
+        }
+        statuses
+    }
+
+    fn expand_repeats(&mut self, values: &BTreeMap<String, VariableValue>) {
+        if let Some(list) = self.repeat_for.clone() {
+            let items = match values.get(&list) {
+                Some(VariableValue::List(items)) => items.as_slice(),
+                _ => &[],
+            };
+            let template = std::mem::take(&mut self.children);
+            let mut ids = HashSet::new();
+            for task in template.iter() {
+                task.collect_ids(&mut ids);
+            }
+
+            let mut instances = Vec::new();
+            for (index, item) in items.iter().enumerate() {
+                let id = Uuid::new_v5(&self.id, index.to_string().as_bytes());
+                let values = BTreeMap::from([(list.clone(), item.clone())]);
+                let mut children = template.clone();
+                for child in children.iter_mut() {
+                    child.instantiate(&id, &ids, &values);
+                }
+                instances.push(TaskStatus {
+                    id,
+                    title: item.clone(),
+                    optional: false,
+                    visible_if: None,
+                    required_if: None,
+                    depends_on: Vec::new(),
+                    // Order applies to tasks within each instance.
+                    sequential: self.sequential,
+                    repeat_for: None,
+                    item: Some(item.clone()),
+                    progress: None,
+                    include: None,
+                    visible: true,
+                    required: true,
+                    state: State::NotStarted,
+                    reason: None,
+                    input: None,
+                    value: None,
+                    assignee: None,
+                    due_at: None,
+                    requires_verification: false,
+                    performed_by: None,
+                    verified_by: None,
+                    requires_signature: false,
+                    signature: None,
+                    children,
+                });
+            }
+            self.children = instances;
+            self.sequential = false;
+        }
+        for child in self.children.iter_mut() {
+            child.expand_repeats(values);
+        }
+    }
+
+    fn collect_ids(&self, ids: &mut HashSet<Uuid>) {
+        ids.insert(self.id);
+        for child in self.children.iter() {
+            child.collect_ids(ids);
+        }
+    }
+
+    /// Derives new ids from `namespace` for the task, its sub-tasks and references to any task in
+    /// `ids`, and replaces placeholders in titles with `values`.
+    fn instantiate(
+        &mut self,
+        namespace: &Uuid,
+        ids: &HashSet<Uuid>,
+        values: &BTreeMap<String, String>,
+    ) {
+        let remap = |id: &mut Uuid| {
+            if ids.contains(id) {
+                *id = Uuid::new_v5(namespace, id.as_bytes());
+            }
+        };
+        remap(&mut self.id);
+        for condition in [&mut self.visible_if, &mut self.required_if]
+            .into_iter()
+            .flatten()
+        {
+            remap(&mut condition.task_id);
+        }
+        self.depends_on.iter_mut().for_each(remap);
+        self.title = render(&self.title, values);
+        for child in self.children.iter_mut() {
+            child.instantiate(namespace, ids, values);
+        }
+    }
+
+    fn find(&self, id: Uuid) -> Option<&TaskStatus> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|t| t.find(id))
+    }
+
+    /// Returns the task with the given id preceded by all its ancestors.
+    fn path_to(&self, id: Uuid) -> Option<Vec<&TaskStatus>> {
+        if self.id == id {
+            return Some(vec![self]);
+        }
+        self.children.iter().find_map(|t| {
+            let mut path = t.path_to(id)?;
+            path.insert(0, self);
+            Some(path)
+        })
+    }
+
+    fn summary(&self) -> TaskSummary {
+        TaskSummary {
+            id: self.id,
+            title: self.title.clone(),
+        }
+    }
+
+    fn find_mut(&mut self, id: Uuid) -> Option<&mut TaskStatus> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|t| t.find_mut(id))
+    }
+
+    /// Validates `value` against the task's input and stores it.
+    ///
+    /// Completing a task with an input requires a value, either a new or a previously captured one.
+    fn capture_value(&mut self, state: State, value: Option<Value>) -> Result<(), TransitionError> {
+        match (&self.input, value) {
+            (None, Some(_)) => Err(TransitionError::InvalidValue {
+                reason: "task does not take a value".to_owned(),
+            }),
+            (Some(input), Some(value)) => {
+                input
+                    .validate_value(&value)
+                    .map_err(|reason| TransitionError::InvalidValue { reason })?;
+                self.value = Some(value);
+                Ok(())
+            }
+            (Some(_), None) if state == State::Done && self.value.is_none() => {
+                Err(TransitionError::ValueRequired)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Evaluates conditions of this task and its descendants in order.
+    ///
+    /// `values` collects answers of visible tasks, so only those can affect later tasks.
+    fn evaluate_conditions(&mut self, parent_visible: bool, values: &mut HashMap<Uuid, Value>) {
+        self.visible = parent_visible
+            && self
+                .visible_if
+                .as_ref()
+                .map_or(true, |condition| condition.holds(values));
+        self.required = match &self.required_if {
+            Some(condition) => condition.holds(values),
+            None => !self.optional,
+        };
+        if let (true, Some(value)) = (self.visible, &self.value) {
+            values.insert(self.id, value.clone());
+        }
+        for child in self.children.iter_mut() {
+            child.evaluate_conditions(self.visible, values);
+        }
+    }
+
+    /// Recomputes the states of this task and its descendants from their leaf tasks.
+    fn derive_state(&mut self) {
+        if self.children.is_empty() {
+            return;
+        }
+        self.children.iter_mut().for_each(TaskStatus::derive_state);
+        if self.item.is_some() {
+            self.progress = Some(Progress::of(&self.children));
+        }
+
+        // Hidden children never count, optional ones only when there is nothing else to complete.
+        let visible: Vec<&TaskStatus> = self.children.iter().filter(|t| t.visible).collect();
+        let required: Vec<State> = visible
+            .iter()
+            .filter(|t| t.required)
+            .map(|t| t.state)
+            .collect();
+        let states = if required.is_empty() {
+            visible.iter().map(|t| t.state).collect()
+        } else {
+            required
+        };
+
+        self.state = if states.iter().all(|s| *s == State::Skipped) {
+            State::Skipped
+        } else if states.iter().all(State::is_complete) {
+            State::Done
+        } else if states.contains(&State::Failed) {
+            State::Failed
+        } else if states.contains(&State::Blocked) {
+            State::Blocked
+        } else if states.iter().all(|s| *s == State::NotStarted) {
+            State::NotStarted
+        } else {
+            State::InProgress
+        };
+        if self.state != State::Skipped {
+            self.reason = None;
+        }
+    }
+
+    /// Skips all descendants that are not complete yet.
+    fn skip_remaining(&mut self, reason: &Option<String>, signature: &Option<Signature>) {
+        if self.children.is_empty() {
+            if !self.state.is_complete() {
+                self.state = State::Skipped;
+                self.reason.clone_from(reason);
+                if self.requires_signature {
+                    self.signature.clone_from(signature);
+                }
+            }
+        } else {
+            self.reason.clone_from(reason);
+            for child in self.children.iter_mut() {
+                child.skip_remaining(reason, signature);
+            }
+        }
+    }
+
+    /// Whether skipping the remaining sub-tasks would skip a task that requires a signature.
+    fn remaining_require_signature(&self) -> bool {
+        if self.children.is_empty() {
+            self.requires_signature && !self.state.is_complete()
+        } else {
+            self.children
+                .iter()
+                .any(|c| c.remaining_require_signature())
+        }
+    }
+
+    fn collect_assigned<'a>(&'a self, username: &str, tasks: &mut Vec<&'a TaskStatus>) {
+        if !self.visible {
+            return;
+        }
+        if self.assignee.as_deref() == Some(username) && !self.state.is_complete() {
+            tasks.push(self);
+        }
+        for child in self.children.iter() {
+            child.collect_assigned(username, tasks);
+        }
+    }
+
+    /// Visits required leaf tasks, i.e. visible ones that are not optional nor inside an optional task.
+    fn for_each_required_leaf(&self, f: &mut impl FnMut(&TaskStatus)) {
+        if !self.visible || !self.required {
+            return;
+        }
+        if self.children.is_empty() {
+            f(self);
+        } else {
+            for child in self.children.iter() {
+                child.for_each_required_leaf(f);
+            }
+        }
+    }
+
+    /// Visits visible leaf tasks, including optional ones.
+    fn for_each_visible_leaf(&self, f: &mut impl FnMut(&TaskStatus)) {
+        if !self.visible {
+            return;
+        }
+        if self.children.is_empty() {
+            f(self);
+        } else {
+            for child in self.children.iter() {
+                child.for_each_visible_leaf(f);
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Execution {
+    pub id: Uuid,
+    pub checklist_id: Uuid,
+    /// Version of the checklist the execution was started from.
+    pub checklist_version: i32,
+    /// Title with placeholders replaced by `variables`, like titles of the tasks.
+    pub title: String,
+    /// Values of the checklist's variables the execution was started with.
+    #[serde(default)]
+    pub variables: BTreeMap<String, VariableValue>,
+    /// Batch the execution was started in, together with its target.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Team the execution belongs to, inherited from its checklist.
+    pub team_id: Option<Uuid>,
+    pub task_statuses: Vec<TaskStatus>,
+    pub state: State,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub progress: Progress,
+    #[serde(default)]
+    pub comments: CommentCounts,
+}
+
+/// Validates a transition and returns the reason to store alongside the new state.
+fn check_transition(
+    current: State,
+    next: State,
+    reason: Option<String>,
+) -> Result<Option<String>, TransitionError> {
+    if !current.can_transition_to(next) {
+        return Err(TransitionError::InvalidTransition {
+            from: current,
+            to: next,
+        });
+    }
+    let reason = reason.filter(|r| !r.trim().is_empty());
+    if next == State::Skipped && reason.is_none() {
+        return Err(TransitionError::ReasonRequired);
+    }
+    Ok(reason)
+}
+
+impl Execution {
+    fn find_task(&self, id: Uuid) -> Option<&TaskStatus> {
+        self.task_statuses.iter().find_map(|t| t.find(id))
+    }
+
+    fn find_task_mut(&mut self, task: TaskRef) -> Option<&mut TaskStatus> {
+        match task {
+            TaskRef::Id(id) => self.task_statuses.iter_mut().find_map(|t| t.find_mut(id)),
+            TaskRef::Index(index) => self.task_statuses.get_mut(index),
+        }
+    }
+
+    /// Lists tasks that have to be complete before the given one can be done, i.e. its
+    /// dependencies, dependencies of its sections and earlier tasks of sequential sections.
+    fn blocking_tasks(&self, id: Uuid) -> Vec<TaskSummary> {
+        let path = self
+            .task_statuses
+            .iter()
+            .find_map(|t| t.path_to(id))
+            .unwrap_or_default();
+
+        let mut blocking: Vec<&TaskStatus> = Vec::new();
+        for (index, task) in path.iter().enumerate() {
+            blocking.extend(task.depends_on.iter().filter_map(|id| self.find_task(*id)));
+            if let (true, Some(next)) = (task.sequential, path.get(index + 1)) {
+                blocking.extend(task.children.iter().take_while(|t| t.id != next.id));
+            }
+        }
+
+        let mut summaries: Vec<TaskSummary> = Vec::new();
+        for task in blocking {
+            if task.visible
+                && !task.state.is_complete()
+                && !summaries.iter().any(|s| s.id == task.id)
+            {
+                summaries.push(task.summary());
+            }
+        }
+        summaries
+    }
+
+    /// Resolves a reference to the id of one of the execution's tasks.
+    pub fn task_id(&self, task: TaskRef) -> Result<Uuid, TransitionError> {
+        let task = match task {
+            TaskRef::Id(id) => self.find_task(id),
+            TaskRef::Index(index) => self.task_statuses.get(index),
+        };
+        task.map(|t| t.id).ok_or(TransitionError::TaskNotFound)
+    }
+
+    fn for_each_required_leaf(&self, mut f: impl FnMut(&TaskStatus)) {
+        for task in self.task_statuses.iter() {
+            task.for_each_required_leaf(&mut f);
+        }
+    }
+
+    fn for_each_visible_leaf(&self, mut f: impl FnMut(&TaskStatus)) {
+        for task in self.task_statuses.iter() {
+            task.for_each_visible_leaf(&mut f);
+        }
+    }
+
+    /// Re-evaluates task conditions and recomputes states of tasks with sub-tasks and the overall
+    /// progress.
+    pub fn refresh(&mut self) {
+        let mut values = HashMap::new();
+        for task in self.task_statuses.iter_mut() {
+            task.evaluate_conditions(true, &mut values);
+        }
+        self.task_statuses
+            .iter_mut()
+            .for_each(TaskStatus::derive_state);
+
+        self.progress = Progress::of(&self.task_statuses);
+    }
+
+    /// Changes state of a task on behalf of the user with the given username.
+    pub fn update_task_state(
+        &mut self,
+        task: TaskRef,
+        update: TaskUpdate,
+        username: &str,
+    ) -> Result<TaskStatus, TransitionError> {
+        self.change_task_state(task, update, username, None)
+    }
+
+    /// Updates state of a task with the user's signature, which tasks requiring one can only be
+    /// changed with.
+    pub fn update_signed_task_state(
+        &mut self,
+        task: TaskRef,
+        update: TaskUpdate,
+        signature: Signature,
+    ) -> Result<TaskStatus, TransitionError> {
+        let username = signature.username.clone();
+        self.change_task_state(task, update, &username, Some(signature))
+    }
+
+    fn change_task_state(
+        &mut self,
+        task: TaskRef,
+        update: TaskUpdate,
+        username: &str,
+        signature: Option<Signature>,
+    ) -> Result<TaskStatus, TransitionError> {
+        let TaskUpdate {
+            state,
+            reason,
+            value,
+        } = update;
+        let task_id = self.task_id(task)?;
+        if self.state.is_finished() {
+            return Err(TransitionError::ExecutionFinished { state: self.state });
+        }
+        let blocking = self.blocking_tasks(task_id);
+        let task = self
+            .find_task_mut(TaskRef::Id(task_id))
+            .expect("task was found above");
+        if !task.visible {
+            return Err(TransitionError::TaskHidden);
+        }
+        if task.children.is_empty() && state == State::Done && !blocking.is_empty() {
+            return Err(TransitionError::BlockedByTasks { tasks: blocking });
+        }
+
+        if task.children.is_empty() {
+            if task.requires_signature && state != State::NotStarted && signature.is_none() {
+                return Err(TransitionError::SignatureRequired);
+            }
+            let reason = check_transition(task.state, state, reason)?;
+            task.capture_value(state, value)?;
+            task.reason = reason;
+            task.state = state;
+            // Any change has to be verified again.
+            task.performed_by = (state != State::NotStarted).then(|| username.to_owned());
+            task.verified_by = None;
+            task.signature =
+                signature.filter(|_| task.requires_signature && state != State::NotStarted);
+        } else if value.is_some() {
+            return Err(TransitionError::InvalidValue {
+                reason: "task does not take a value".to_owned(),
+            });
+        } else if state == State::Skipped {
+            if task.remaining_require_signature() && signature.is_none() {
+                return Err(TransitionError::SignatureRequired);
+            }
+            let reason = check_transition(task.state, state, reason)?;
+            task.skip_remaining(&reason, &signature);
+        } else {
+            return Err(TransitionError::DerivedState);
+        }
+        self.refresh();
+        if self.state == State::NotStarted {
+            self.state = State::InProgress;
+        }
+        Ok(self
+            .find_task(task_id)
+            .expect("updated task is part of the execution")
+            .clone())
+    }
+
+    /// Returns the user responsible for a task, i.e. the assignee of the task or of its closest
+    /// assigned section.
+    pub fn assignee_of(&self, task: TaskRef) -> Result<Option<&str>, TransitionError> {
+        let task_id = self.task_id(task)?;
+        let path = self
+            .task_statuses
+            .iter()
+            .find_map(|t| t.path_to(task_id))
+            .unwrap_or_default();
+        Ok(path.into_iter().rev().find_map(|t| t.assignee.as_deref()))
+    }
+
+    pub fn assign_task(
+        &mut self,
+        task: TaskRef,
+        assignment: Assignment,
+    ) -> Result<TaskStatus, TransitionError> {
+        if self.state.is_finished() {
+            return Err(TransitionError::ExecutionFinished { state: self.state });
+        }
+        let task = self
+            .find_task_mut(task)
+            .ok_or(TransitionError::TaskNotFound)?;
+        task.assignee = assignment.assignee;
+        task.due_at = assignment.due_at;
+        Ok(task.clone())
+    }
+
+    /// Records verification of a done task by a user other than the one who performed it.
+    pub fn verify_task(
+        &mut self,
+        task: TaskRef,
+        username: &str,
+    ) -> Result<TaskStatus, TransitionError> {
+        if self.state.is_finished() {
+            return Err(TransitionError::ExecutionFinished { state: self.state });
+        }
+        let task = self
+            .find_task_mut(task)
+            .ok_or(TransitionError::TaskNotFound)?;
+        if !task.requires_verification {
+            return Err(TransitionError::VerificationNotRequired);
+        }
+        if task.state != State::Done {
+            return Err(TransitionError::NotPerformed);
+        }
+        if task.performed_by.as_deref() == Some(username) {
+            return Err(TransitionError::SelfVerification);
+        }
+        if task.verified_by.is_some() {
+            return Err(TransitionError::AlreadyVerified);
+        }
+        task.verified_by = Some(username.to_owned());
+        Ok(task.clone())
+    }
+
+    /// Lists visible tasks assigned to the user that are not complete yet.
+    pub fn assigned_tasks(&self, username: &str) -> Vec<AssignedTask> {
+        let mut tasks = Vec::new();
+        for task in self.task_statuses.iter() {
+            task.collect_assigned(username, &mut tasks);
+        }
+        tasks
+            .into_iter()
+            .map(|t| AssignedTask {
+                execution_id: self.id,
+                execution_title: self.title.clone(),
+                id: t.id,
+                title: t.title.clone(),
+                state: t.state,
+                due_at: t.due_at,
+            })
+            .collect()
+    }
+
+    pub fn update_state(
+        &mut self,
+        state: State,
+        reason: Option<String>,
+    ) -> Result<(), TransitionError> {
+        let reason = check_transition(self.state, state, reason)?;
+        if state == State::Done {
+            let mut incomplete = Vec::new();
+            self.for_each_required_leaf(|t| {
+                if !t.state.is_complete() {
+                    incomplete.push(t.summary());
+                }
+            });
+            if !incomplete.is_empty() {
+                return Err(TransitionError::IncompleteTasks { tasks: incomplete });
+            }
+            // Optional tasks do not have to be done, but once they are, they have to be verified
+            let mut unverified = Vec::new();
+            self.for_each_visible_leaf(|t| {
+                if t.requires_verification && t.state == State::Done && t.verified_by.is_none() {
+                    unverified.push(t.summary());
+                }
+            });
+            if !unverified.is_empty() {
+                return Err(TransitionError::UnverifiedTasks { tasks: unverified });
+            }
+        }
+        self.reason = reason;
+        self.state = state;
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExecutionSummary {
+    pub id: Uuid,
+    pub checklist_id: Uuid,
+    pub title: String,
+    pub state: State,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn update(state: State, reason: Option<String>) -> TaskUpdate {
+        TaskUpdate {
+            state,
+            reason,
+            value: None,
+        }
+    }
+
+    fn execution(tasks: serde_json::Value) -> Execution {
+        let tasks: Vec<Task> = serde_json::from_value(tasks).unwrap();
+        let mut execution = Execution {
+            id: Uuid::new_v4(),
+            checklist_id: Uuid::new_v4(),
+            checklist_version: 1,
+            title: "Test".to_owned(),
+            variables: BTreeMap::new(),
+            batch_id: None,
+            target: None,
+            team_id: None,
+            task_statuses: tasks.iter().map(TaskStatus::from).collect(),
+            state: State::NotStarted,
+            reason: None,
+            progress: Progress::default(),
+            comments: CommentCounts::default(),
+        };
+        execution.refresh();
+        execution
+    }
+
+    fn flat_execution(tasks: &[(&str, bool)]) -> Execution {
+        execution(
+            tasks
+                .iter()
+                .map(|(title, optional)| json!({"title": title, "optional": optional}))
+                .collect(),
+        )
+    }
+
+    /// Three sections with two tasks each; the last section is optional.
+    fn nested_execution() -> Execution {
+        execution(json!([
+            {"title": "Cabin", "children": [{"title": "Seats"}, {"title": "Belts"}]},
+            {"title": "Doors", "children": [
+                {"title": "Front", "children": [{"title": "Left"}, {"title": "Right"}]}
+            ]},
+            {"title": "Extras", "optional": true, "children": [{"title": "Snacks"}]}
+        ]))
+    }
+
+    fn task_id(execution: &Execution, path: &[usize]) -> Uuid {
+        let mut task = &execution.task_statuses[path[0]];
+        for index in &path[1..] {
+            task = &task.children[*index];
+        }
+        task.id
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        for state in [
+            State::NotStarted,
+            State::InProgress,
+            State::Skipped,
+            State::Blocked,
+            State::Failed,
+            State::Done,
+        ] {
+            assert_eq!(Ok(state), State::from_str(state.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_state_from_wrong_str() {
+        assert_eq!(Err(InvalidState), State::from_str("finished"));
+    }
+
+    #[test]
+    fn test_blocked_task_cannot_be_done() {
+        let mut execution = flat_execution(&[("a", false)]);
+        execution
+            .update_task_state(TaskRef::Index(0), update(State::Blocked, None), "jozin")
+            .unwrap();
+
+        assert_eq!(
+            Err(TransitionError::InvalidTransition {
+                from: State::Blocked,
+                to: State::Done
+            }),
+            execution.update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+        );
+    }
+
+    #[test]
+    fn test_skipping_requires_reason() {
+        let mut execution = flat_execution(&[("a", false)]);
+
+        assert_eq!(
+            Err(TransitionError::ReasonRequired),
+            execution.update_task_state(
+                TaskRef::Index(0),
+                update(State::Skipped, Some(" ".to_owned())),
+                "jozin"
+            )
+        );
+        let task = execution
+            .update_task_state(
+                TaskRef::Index(0),
+                update(State::Skipped, Some("not needed".to_owned())),
+                "jozin",
+            )
+            .unwrap();
+        assert_eq!(Some("not needed".to_owned()), task.reason);
+    }
+
+    #[test]
+    fn test_updating_task_starts_execution() {
+        let mut execution = flat_execution(&[("a", false)]);
+        execution
+            .update_task_state(TaskRef::Index(0), update(State::InProgress, None), "jozin")
+            .unwrap();
+
+        assert_eq!(State::InProgress, execution.state);
+    }
+
+    #[test]
+    fn test_execution_cannot_be_done_with_incomplete_required_tasks() {
+        let mut execution = flat_execution(&[("a", false), ("b", false), ("c", true)]);
+        execution
+            .update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+            .unwrap();
+
+        assert_eq!(
+            Err(TransitionError::IncompleteTasks {
+                tasks: vec![TaskSummary {
+                    id: execution.task_statuses[1].id,
+                    title: "b".to_owned()
+                }]
+            }),
+            execution.update_state(State::Done, None)
+        );
+
+        execution
+            .update_task_state(
+                TaskRef::Index(1),
+                update(State::Skipped, Some("n/a".to_owned())),
+                "jozin",
+            )
+            .unwrap();
+        assert_eq!(Ok(()), execution.update_state(State::Done, None));
+    }
+
+    #[test]
+    fn test_task_ref_from_str() {
+        let id = Uuid::new_v4();
+        assert_eq!(Ok(TaskRef::Id(id)), TaskRef::from_str(&id.to_string()));
+        assert_eq!(Ok(TaskRef::Index(3)), TaskRef::from_str("3"));
+        assert_eq!(Err(InvalidTaskRef), TaskRef::from_str("-1"));
+    }
+
+    #[test]
+    fn test_task_can_be_updated_by_id() {
+        let mut execution = flat_execution(&[("a", false), ("b", false)]);
+        let id = execution.task_statuses[1].id;
+
+        let task = execution
+            .update_task_state(TaskRef::Id(id), update(State::Done, None), "jozin")
+            .unwrap();
+        assert_eq!("b", task.title);
+        assert_eq!(
+            Err(TransitionError::TaskNotFound),
+            execution.update_task_state(
+                TaskRef::Id(Uuid::new_v4()),
+                update(State::Done, None),
+                "jozin"
+            )
+        );
+    }
+
+    #[test]
+    fn test_finished_execution_rejects_task_changes() {
+        let mut execution = flat_execution(&[("a", true)]);
+        execution.update_state(State::Done, None).unwrap();
+
+        assert_eq!(
+            Err(TransitionError::ExecutionFinished { state: State::Done }),
+            execution.update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+        );
+    }
+
+    #[test]
+    fn test_parent_state_is_derived_from_children() {
+        let mut execution = nested_execution();
+        let left = task_id(&execution, &[1, 0, 0]);
+        let right = task_id(&execution, &[1, 0, 1]);
+
+        execution
+            .update_task_state(TaskRef::Id(left), update(State::Done, None), "jozin")
+            .unwrap();
+        assert_eq!(State::InProgress, execution.task_statuses[1].state);
+        assert_eq!(
+            State::InProgress,
+            execution.task_statuses[1].children[0].state
+        );
+        assert_eq!(State::NotStarted, execution.task_statuses[0].state);
+
+        execution
+            .update_task_state(TaskRef::Id(right), update(State::Blocked, None), "jozin")
+            .unwrap();
+        assert_eq!(State::Blocked, execution.task_statuses[1].state);
+
+        execution
+            .update_task_state(TaskRef::Id(right), update(State::Done, None), "jozin")
+            .unwrap_err();
+        execution
+            .update_task_state(TaskRef::Id(right), update(State::InProgress, None), "jozin")
+            .unwrap();
+        let doors = execution
+            .update_task_state(TaskRef::Id(right), update(State::Done, None), "jozin")
+            .unwrap();
+        assert_eq!(State::Done, doors.state);
+        assert_eq!(State::Done, execution.task_statuses[1].state);
+    }
+
+    #[test]
+    fn test_parent_state_cannot_be_set_directly() {
+        let mut execution = nested_execution();
+
+        assert_eq!(
+            Err(TransitionError::DerivedState),
+            execution.update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+        );
+    }
+
+    #[test]
+    fn test_skipping_parent_skips_remaining_children() {
+        let mut execution = nested_execution();
+        let seats = task_id(&execution, &[0, 0]);
+        execution
+            .update_task_state(TaskRef::Id(seats), update(State::Done, None), "jozin")
+            .unwrap();
+
+        let cabin = execution
+            .update_task_state(
+                TaskRef::Index(0),
+                update(State::Skipped, Some("n/a".to_owned())),
+                "jozin",
+            )
+            .unwrap();
+
+        assert_eq!(State::Done, cabin.state);
+        assert_eq!(State::Done, cabin.children[0].state);
+        assert_eq!(State::Skipped, cabin.children[1].state);
+        assert_eq!(Some("n/a".to_owned()), cabin.children[1].reason);
+    }
+
+    #[test]
+    fn test_progress_counts_required_leaf_tasks() {
+        let mut execution = nested_execution();
+        assert_eq!(
+            Progress {
+                completed: 0,
+                total: 4
+            },
+            execution.progress
+        );
+
+        for path in [[0, 0], [0, 1]] {
+            let id = task_id(&execution, &path);
+            execution
+                .update_task_state(TaskRef::Id(id), update(State::Done, None), "jozin")
+                .unwrap();
+        }
+        assert_eq!(
+            Progress {
+                completed: 2,
+                total: 4
+            },
+            execution.progress
+        );
+        assert_eq!(
+            Err(TransitionError::IncompleteTasks {
+                tasks: vec![
+                    TaskSummary {
+                        id: task_id(&execution, &[1, 0, 0]),
+                        title: "Left".to_owned()
+                    },
+                    TaskSummary {
+                        id: task_id(&execution, &[1, 0, 1]),
+                        title: "Right".to_owned()
+                    }
+                ]
+            }),
+            execution.update_state(State::Done, None)
+        );
+    }
+
+    #[test]
+    fn test_completing_task_with_input_requires_value() {
+        let mut execution = execution(json!([
+            {"title": "Tyre pressure", "input": {"type": "number", "unit": "psi", "min": 20}}
+        ]));
+
+        assert_eq!(
+            Err(TransitionError::ValueRequired),
+            execution.update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+        );
+        assert_eq!(
+            Err(TransitionError::InvalidValue {
+                reason: "value cannot be lower than 20".to_owned()
+            }),
+            execution.update_task_state(
+                TaskRef::Index(0),
+                TaskUpdate {
+                    value: Some(json!(12)),
+                    ..update(State::Done, None)
+                },
+                "jozin"
+            )
+        );
+
+        let task = execution
+            .update_task_state(
+                TaskRef::Index(0),
+                TaskUpdate {
+                    value: Some(json!(32)),
+                    ..update(State::InProgress, None)
+                },
+                "jozin",
+            )
+            .unwrap();
+        assert_eq!(Some(json!(32)), task.value);
+        let task = execution
+            .update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+            .unwrap();
+        assert_eq!(Some(json!(32)), task.value);
+    }
+
+    #[test]
+    fn test_task_without_input_rejects_value() {
+        let mut execution = flat_execution(&[("a", false)]);
+
+        assert_eq!(
+            Err(TransitionError::InvalidValue {
+                reason: "task does not take a value".to_owned()
+            }),
+            execution.update_task_state(
+                TaskRef::Index(0),
+                TaskUpdate {
+                    value: Some(json!(true)),
+                    ..update(State::Done, None)
+                },
+                "jozin"
+            )
+        );
+    }
+
+    #[test]
+    fn test_conditions_follow_earlier_answers() {
+        let mut execution = execution(json!([
+            {"id": "7e3d6c57-51a4-4a8e-9f6c-7c1bfc8c1a01", "title": "Water detected",
+                "input": {"type": "boolean"}},
+            {"title": "Drain fuel sample", "visible_if": {
+                "task_id": "7e3d6c57-51a4-4a8e-9f6c-7c1bfc8c1a01", "equals": true
+            }},
+            {"title": "Report", "optional": true, "required_if": {
+                "task_id": "7e3d6c57-51a4-4a8e-9f6c-7c1bfc8c1a01", "equals": true
+            }}
+        ]));
+        assert!(!execution.task_statuses[1].visible);
+        assert!(!execution.task_statuses[2].required);
+        assert_eq!(
+            Err(TransitionError::TaskHidden),
+            execution.update_task_state(TaskRef::Index(1), update(State::Done, None), "jozin")
+        );
+
+        execution
+            .update_task_state(
+                TaskRef::Index(0),
+                TaskUpdate {
+                    value: Some(json!(true)),
+                    ..update(State::Done, None)
+                },
+                "jozin",
+            )
+            .unwrap();
+        assert!(execution.task_statuses[1].visible);
+        assert!(execution.task_statuses[2].required);
+        assert_eq!(
+            Progress {
+                completed: 1,
+                total: 3
+            },
+            execution.progress
+        );
+
+        execution
+            .update_task_state(
+                TaskRef::Index(0),
+                TaskUpdate {
+                    value: Some(json!(false)),
+                    ..update(State::Done, None)
+                },
+                "jozin",
+            )
+            .unwrap();
+        assert!(!execution.task_statuses[1].visible);
+        assert_eq!(Ok(()), execution.update_state(State::Done, None));
+    }
+
+    #[test]
+    fn test_hidden_children_do_not_count_towards_parent_state() {
+        let mut execution = execution(json!([
+            {"id": "0b0e1f43-3e43-4cf4-9d43-bb5d2c1c4d10", "title": "Damage",
+                "input": {"type": "choice", "options": ["none", "dent"]}},
+            {"title": "Inspection", "children": [
+                {"title": "Walk around"},
+                {"title": "Photograph dent", "visible_if": {
+                    "task_id": "0b0e1f43-3e43-4cf4-9d43-bb5d2c1c4d10", "equals": "dent"
+                }}
+            ]}
+        ]));
+        let walk_around = task_id(&execution, &[1, 0]);
+
+        execution
+            .update_task_state(TaskRef::Id(walk_around), update(State::Done, None), "jozin")
+            .unwrap();
+
+        assert_eq!(State::Done, execution.task_statuses[1].state);
+    }
+
+    #[test]
+    fn test_task_cannot_be_done_before_its_dependencies() {
+        let mut execution = execution(json!([
+            {"id": "5a7c9c1e-3c52-4b6e-8a57-0d6f1c0f2b01", "title": "Drain traffic"},
+            {"title": "Deploy", "children": [{"title": "Restart service"}],
+                "depends_on": ["5a7c9c1e-3c52-4b6e-8a57-0d6f1c0f2b01"]}
+        ]));
+        let restart = task_id(&execution, &[1, 0]);
+
+        assert_eq!(
+            Err(TransitionError::BlockedByTasks {
+                tasks: vec![TaskSummary {
+                    id: task_id(&execution, &[0]),
+                    title: "Drain traffic".to_owned()
+                }]
+            }),
+            execution.update_task_state(TaskRef::Id(restart), update(State::Done, None), "jozin")
+        );
+        execution
+            .update_task_state(
+                TaskRef::Id(restart),
+                update(State::InProgress, None),
+                "jozin",
+            )
+            .unwrap();
+
+        execution
+            .update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+            .unwrap();
+        execution
+            .update_task_state(TaskRef::Id(restart), update(State::Done, None), "jozin")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sequential_section_is_completed_in_order() {
+        let mut execution = execution(json!([
+            {"title": "Runbook", "sequential": true, "children": [
+                {"title": "Step 1"},
+                {"title": "Step 2", "optional": true},
+                {"title": "Step 3"}
+            ]}
+        ]));
+        let step = |index| TaskRef::Id(task_id(&execution, &[0, index]));
+        let (first, second, third) = (step(0), step(1), step(2));
+
+        let error = execution
+            .update_task_state(third, update(State::Done, None), "jozin")
+            .unwrap_err();
+        assert_eq!(
+            TransitionError::BlockedByTasks {
+                tasks: vec![
+                    execution.task_statuses[0].children[0].summary(),
+                    execution.task_statuses[0].children[1].summary()
+                ]
+            },
+            error
+        );
+
+        execution
+            .update_task_state(first, update(State::Done, None), "jozin")
+            .unwrap();
+        execution
+            .update_task_state(
+                second,
+                update(State::Skipped, Some("n/a".to_owned())),
+                "jozin",
+            )
+            .unwrap();
+        execution
+            .update_task_state(third, update(State::Done, None), "jozin")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_repeated_section_references_stay_within_instance() {
+        let tasks: Vec<Task> = serde_json::from_value(json!([
+            {"title": "Rooms", "repeat_for": "rooms", "children": [
+                {"id": "c2b3b0a4-8d7e-4a53-9b4b-1f2c3d4e5f60", "title": "Smoke detector in {{rooms}}",
+                    "input": {"type": "boolean"}},
+                {"title": "Replace battery", "visible_if": {
+                    "task_id": "c2b3b0a4-8d7e-4a53-9b4b-1f2c3d4e5f60", "equals": false
+                }}
+            ]}
+        ]))
+        .unwrap();
+        let values = BTreeMap::from([(
+            "rooms".to_owned(),
+            VariableValue::List(vec!["Kitchen".to_owned(), "Hall".to_owned()]),
+        )]);
+
+        let statuses = TaskStatus::from_tasks(&tasks, &values);
+
+        let (kitchen, hall) = (&statuses[0].children[0], &statuses[0].children[1]);
+        assert_eq!(Some("Hall".to_owned()), hall.item);
+        assert_eq!("Smoke detector in Kitchen", kitchen.children[0].title);
+        assert_ne!(kitchen.children[0].id, hall.children[0].id);
+        assert_eq!(
+            Some(hall.children[0].id),
+            hall.children[1].visible_if.as_ref().map(|c| c.task_id)
+        );
+    }
+
+    #[test]
+    fn test_tasks_inherit_assignee_of_their_section() {
+        let mut execution = nested_execution();
+        let doors = task_id(&execution, &[1]);
+        let left = task_id(&execution, &[1, 0, 0]);
+        let seats = task_id(&execution, &[0, 0]);
+        execution
+            .assign_task(
+                TaskRef::Id(doors),
+                Assignment {
+                    assignee: Some("krtek".to_owned()),
+                    due_at: None,
+                },
+            )
+            .unwrap();
+
+        assert_eq!(Ok(Some("krtek")), execution.assignee_of(TaskRef::Id(left)));
+        assert_eq!(Ok(None), execution.assignee_of(TaskRef::Id(seats)));
+    }
+
+    #[test]
+    fn test_assigned_tasks_include_only_open_tasks() {
+        let mut execution = flat_execution(&[("Fuel", false), ("Tyres", false)]);
+        for index in 0..2 {
+            execution
+                .assign_task(
+                    TaskRef::Index(index),
+                    Assignment {
+                        assignee: Some("krtek".to_owned()),
+                        due_at: None,
+                    },
+                )
+                .unwrap();
+        }
+        execution
+            .update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+            .unwrap();
+
+        let tasks = execution.assigned_tasks("krtek");
+
+        assert_eq!(1, tasks.len());
+        assert_eq!("Tyres", tasks[0].title);
+        assert!(execution.assigned_tasks("jozin").is_empty());
+    }
+
+    #[test]
+    fn test_task_has_to_be_verified_by_another_user() {
+        let mut execution = execution(json!([
+            {"title": "Weigh sample", "requires_verification": true}
+        ]));
+        let task = TaskRef::Index(0);
+
+        assert_eq!(
+            Err(TransitionError::NotPerformed),
+            execution.verify_task(task, "krtek")
+        );
+        execution
+            .update_task_state(task, update(State::Done, None), "jozin")
+            .unwrap();
+        assert_eq!(
+            Err(TransitionError::SelfVerification),
+            execution.verify_task(task, "jozin")
+        );
+        let verified = execution.verify_task(task, "krtek").unwrap();
+
+        assert_eq!(Some("jozin".to_owned()), verified.performed_by);
+        assert_eq!(Some("krtek".to_owned()), verified.verified_by);
+    }
+
+    #[test]
+    fn test_execution_cannot_be_done_with_unverified_tasks() {
+        let mut execution = execution(json!([
+            {"title": "Weigh sample", "requires_verification": true}
+        ]));
+        let task = TaskRef::Index(0);
+        execution
+            .update_task_state(task, update(State::Done, None), "jozin")
+            .unwrap();
+        let task_id = execution.task_statuses[0].id;
+
+        assert_eq!(
+            Err(TransitionError::UnverifiedTasks {
+                tasks: vec![TaskSummary {
+                    id: task_id,
+                    title: "Weigh sample".to_owned()
+                }]
+            }),
+            execution.update_state(State::Done, None)
+        );
+        execution.verify_task(task, "krtek").unwrap();
+        execution
+            .update_task_state(task, update(State::Done, None), "jozin")
+            .unwrap();
+        assert!(execution.update_state(State::Done, None).is_err());
+        execution.verify_task(task, "krtek").unwrap();
+        assert_eq!(Ok(()), execution.update_state(State::Done, None));
+    }
+
+    #[test]
+    fn test_execution_cannot_be_done_with_unverified_optional_tasks() {
+        let mut execution = execution(json!([
+            {"title": "Label sample"},
+            {"title": "Weigh sample", "optional": true, "requires_verification": true}
+        ]));
+        let task = TaskRef::Index(1);
+        execution
+            .update_task_state(TaskRef::Index(0), update(State::Done, None), "jozin")
+            .unwrap();
+        execution
+            .update_task_state(task, update(State::Done, None), "jozin")
+            .unwrap();
+        let task_id = execution.task_statuses[1].id;
+
+        assert_eq!(
+            Err(TransitionError::UnverifiedTasks {
+                tasks: vec![TaskSummary {
+                    id: task_id,
+                    title: "Weigh sample".to_owned()
+                }]
+            }),
+            execution.update_state(State::Done, None)
+        );
+        execution.verify_task(task, "krtek").unwrap();
+        assert_eq!(Ok(()), execution.update_state(State::Done, None));
+    }
+
+    #[test]
+    fn test_task_requiring_signature_cannot_be_changed_without_one() {
+        let mut execution = execution(json!([
+            {"title": "Release batch", "requires_signature": true}
+        ]));
+        let task = TaskRef::Index(0);
+        let signature = Signature {
+            username: "jozin".to_owned(),
+            meaning: SignatureMeaning::Approved,
+            signed_at: Utc::now(),
+        };
+
+        assert_eq!(
+            Err(TransitionError::SignatureRequired),
+            execution.update_task_state(task, update(State::Done, None), "jozin")
+        );
+        let signed = execution
+            .update_signed_task_state(task, update(State::Done, None), signature.clone())
+            .unwrap();
+
+        assert_eq!(Some(signature), signed.signature);
+        assert_eq!(Some("jozin".to_owned()), signed.performed_by);
+    }
+
+    #[test]
+    fn test_skipping_section_with_tasks_requiring_signature_needs_one() {
+        let mut execution = execution(json!([
+            {"title": "Release", "children": [
+                {"title": "Inspect"},
+                {"title": "Approve", "requires_signature": true}
+            ]}
+        ]));
+        let task = TaskRef::Index(0);
+        let signature = Signature {
+            username: "jozin".to_owned(),
+            meaning: SignatureMeaning::Reviewed,
+            signed_at: Utc::now(),
+        };
+
+        assert_eq!(
+            Err(TransitionError::SignatureRequired),
+            execution.update_task_state(
+                task,
+                update(State::Skipped, Some("Cancelled".to_owned())),
+                "jozin"
+            )
+        );
+        let skipped = execution
+            .update_signed_task_state(
+                task,
+                update(State::Skipped, Some("Cancelled".to_owned())),
+                signature.clone(),
+            )
+            .unwrap();
+
+        assert_eq!(None, skipped.children[0].signature);
+        assert_eq!(Some(signature), skipped.children[1].signature);
+    }
+}
@@ -0,0 +1,59 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+/// Access to a single checklist granted to a user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    Read,
+    /// Can also start executions of the checklist.
+    Execute,
+    /// Can also edit the checklist.
+    Edit,
+}
+
+impl Access {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Execute => "execute",
+            Self::Edit => "edit",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[error("invalid access")]
+pub struct InvalidAccess;
+
+impl FromStr for Access {
+    type Err = InvalidAccess;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "execute" => Ok(Self::Execute),
+            "edit" => Ok(Self::Edit),
+            _ => Err(InvalidAccess),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Grant {
+    pub username: String,
+    pub access: Access,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_round_trip() {
+        for access in [Access::Read, Access::Execute, Access::Edit] {
+            assert_eq!(Ok(access), Access::from_str(access.as_str()));
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use secrecy::{ExposeSecret, Secret, SecretString};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+const TOTP_SECRET_LENGTH: usize = 20;
+const TOTP_STEP_SECONDS: i64 = 30;
+const TOTP_DIGITS: u32 = 6;
+/// Codes from this many steps before or after the current one are accepted, to allow for clock
+/// drift between the server and the authenticator.
+const TOTP_ALLOWED_DRIFT: i64 = 1;
+const TOTP_ISSUER: &str = "Checkmate";
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Encodes bytes as RFC 4648 base32 without padding, which is what authenticator apps expect.
+fn base32(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    output
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Shared secret of an RFC 6238 time-based one-time password authenticator.
+pub struct TotpSecret(Secret<Vec<u8>>);
+
+impl TotpSecret {
+    pub fn generate_new() -> Self {
+        let mut secret = vec![0u8; TOTP_SECRET_LENGTH];
+        OsRng.fill_bytes(&mut secret);
+        Self::from_bytes(secret)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(Secret::new(bytes))
+    }
+
+    /// The secret is stored as is, since it is needed to compute codes and there is no key
+    /// management to encrypt it with; access to the database has to be protected accordingly.
+    pub(crate) fn to_database_value(&self) -> Vec<u8> {
+        self.0.expose_secret().clone()
+    }
+
+    /// Secret in the form users type into authenticator apps.
+    pub fn to_base32(&self) -> SecretString {
+        base32(self.0.expose_secret()).into()
+    }
+
+    /// URI authenticator apps can be provisioned with, usually shown as a QR code.
+    pub fn otpauth_uri(&self, username: &str) -> SecretString {
+        format!(
+            "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_STEP_SECONDS}",
+            issuer = percent_encode(TOTP_ISSUER),
+            account = percent_encode(username),
+            secret = self.to_base32().expose_secret(),
+        )
+        .into()
+    }
+
+    fn code_at(&self, step: i64) -> u32 {
+        let mut mac = Hmac::<Sha1>::new_from_slice(self.0.expose_secret())
+            .expect("HMAC accepts keys of any length");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        let offset = (hash[hash.len() - 1] & 0xf) as usize;
+        let truncated = u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap());
+        (truncated & 0x7fff_ffff) % 10u32.pow(TOTP_DIGITS)
+    }
+
+    /// Code an authenticator shows at the given time.
+    pub fn code_at_time(&self, time: DateTime<Utc>) -> String {
+        format!(
+            "{:0width$}",
+            self.code_at(time.timestamp() / TOTP_STEP_SECONDS),
+            width = TOTP_DIGITS as usize
+        )
+    }
+
+    /// Checks a code against the steps around the given time, returning the step it belongs to.
+    /// Codes of steps up to `last_used_step` are rejected, so that a code cannot be used twice.
+    pub fn verify(
+        &self,
+        code: &str,
+        time: DateTime<Utc>,
+        last_used_step: Option<i64>,
+    ) -> Option<i64> {
+        let code = code.trim();
+        if code.len() != TOTP_DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let code: u32 = code.parse().ok()?;
+        let current_step = time.timestamp() / TOTP_STEP_SECONDS;
+        (current_step - TOTP_ALLOWED_DRIFT..=current_step + TOTP_ALLOWED_DRIFT)
+            .filter(|step| last_used_step.map_or(true, |last| *step > last))
+            .find(|step| self.code_at(*step) == code)
+    }
+}
+
+/// Generates a new set of single-use recovery codes, formatted for readability.
+pub fn generate_recovery_codes() -> Vec<SecretString> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; RECOVERY_CODE_LENGTH];
+            OsRng.fill_bytes(&mut bytes);
+            let code: String = bytes
+                .iter()
+                .map(|b| BASE32_ALPHABET[(b & 31) as usize].to_ascii_lowercase() as char)
+                .collect();
+            let (first, second) = code.split_at(RECOVERY_CODE_LENGTH / 2);
+            format!("{first}-{second}").into()
+        })
+        .collect()
+}
+
+/// Only hashes of recovery codes are stored. Codes are compared ignoring case and separators.
+pub fn hash_recovery_code(code: &SecretString) -> Vec<u8> {
+    let normalized: String = code
+        .expose_secret()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    Sha256::digest(normalized.as_bytes()).to_vec()
+}
+
+/// Proof of the second factor when completing a login.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecondFactor {
+    /// Current code of the enrolled authenticator.
+    Code(String),
+    /// One of the recovery codes, which cannot be used again.
+    RecoveryCode(SecretString),
+}
+
+/// Newly provisioned authenticator, to be confirmed with a code before it is required at login.
+#[derive(serde::Serialize)]
+pub struct TotpEnrolment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MfaStatus {
+    pub totp_enabled: bool,
+    pub recovery_codes_left: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn rfc_secret() -> TotpSecret {
+        TotpSecret::from_bytes(b"12345678901234567890".to_vec())
+    }
+
+    #[test]
+    fn test_base32() {
+        assert_eq!("", base32(b""));
+        assert_eq!("MY", base32(b"f"));
+        assert_eq!("MZXW6YTBOI", base32(b"foobar"));
+        assert_eq!(
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+            rfc_secret().to_base32().expose_secret()
+        );
+    }
+
+    #[test]
+    fn test_totp_matches_rfc_6238_vectors() {
+        let secret = rfc_secret();
+        for (timestamp, code) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            let time = Utc.timestamp_opt(timestamp, 0).unwrap();
+            assert_eq!(code, secret.code_at_time(time));
+            assert_eq!(Some(timestamp / 30), secret.verify(code, time, None));
+        }
+    }
+
+    #[test]
+    fn test_totp_accepts_neighbouring_steps_only() {
+        let secret = rfc_secret();
+        let code_time = Utc.timestamp_opt(1111111109, 0).unwrap();
+
+        assert!(secret
+            .verify("081804", code_time + chrono::Duration::seconds(30), None)
+            .is_some());
+        assert!(secret
+            .verify("081804", code_time + chrono::Duration::seconds(90), None)
+            .is_none());
+        assert!(secret.verify("081805", code_time, None).is_none());
+        assert!(secret.verify("81804", code_time, None).is_none());
+    }
+
+    #[test]
+    fn test_totp_rejects_used_step() {
+        let secret = rfc_secret();
+        let time = Utc.timestamp_opt(59, 0).unwrap();
+
+        assert!(secret.verify("287082", time, Some(1)).is_none());
+        assert_eq!(Some(1), secret.verify("287082", time, Some(0)));
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let uri = rfc_secret().otpauth_uri("jan nowak");
+
+        assert_eq!(
+            "otpauth://totp/Checkmate:jan%20nowak?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Checkmate&algorithm=SHA1&digits=6&period=30",
+            uri.expose_secret()
+        );
+    }
+
+    #[test]
+    fn test_recovery_codes_are_compared_loosely() {
+        let codes = generate_recovery_codes();
+        let code = codes[0].expose_secret();
+
+        assert_eq!(RECOVERY_CODE_COUNT, codes.len());
+        assert_eq!(
+            hash_recovery_code(&codes[0]),
+            hash_recovery_code(&code.replace('-', "").to_uppercase().into())
+        );
+        assert_ne!(hash_recovery_code(&codes[0]), hash_recovery_code(&codes[1]));
+    }
+}
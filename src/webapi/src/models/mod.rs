@@ -1,2 +1,11 @@
+pub mod batch;
+pub mod checklist;
+pub mod comment;
+pub mod execution;
+pub mod grant;
+pub mod mfa;
+pub mod session;
 pub mod session_token;
+pub mod share_link;
+pub mod team;
 pub mod user;
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::session_token::SessionToken;
+
+/// Session of a logged in user, identified without its token.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    /// Whether this is the session the request was made with.
+    pub current: bool,
+}
+
+/// What a session is being created from, as reported by the client.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+/// Tokens issued when logging in or refreshing a session.
+#[derive(Clone, Debug)]
+pub struct SessionTokens {
+    /// Short-lived token authorizing requests.
+    pub token: SessionToken,
+    /// Long-lived token that can only be exchanged for new tokens.
+    pub refresh_token: SessionToken,
+}
+
+/// Result of checking a user's password.
+#[derive(Clone, Debug)]
+pub enum LoginOutcome {
+    Authenticated(SessionTokens),
+    /// The user has a second factor enrolled and has to complete the login with it, using the
+    /// challenge token before it expires.
+    MfaRequired {
+        challenge: SessionToken,
+        expires_at: DateTime<Utc>,
+    },
+}
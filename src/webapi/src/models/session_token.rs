@@ -1,5 +1,6 @@
 use rand_core::{OsRng, RngCore};
 use secrecy::{ExposeSecret, Secret, SecretString};
+use sha2::{Digest, Sha256};
 use std::{fmt::Write, iter, str::FromStr};
 
 pub const SESSION_TOKEN_LENGTH: usize = 32;
@@ -49,8 +50,14 @@ impl SessionToken {
         Self(bytes.into())
     }
 
-    pub fn to_database_value(&self) -> Secret<Vec<u8>> {
-        self.0.expose_secret().to_vec().into()
+    /// Only a hash of the token is stored, so that tokens cannot be recovered from the database.
+    pub fn to_database_value(&self) -> Vec<u8> {
+        self.to_hash()
+    }
+
+    /// SHA-256 hash of the token, for tokens that are stored only to be recognised later.
+    pub fn to_hash(&self) -> Vec<u8> {
+        Sha256::digest(self.0.expose_secret()).to_vec()
     }
 
     pub(crate) fn to_secret_string(&self) -> SecretString {
@@ -103,6 +110,22 @@ mod tests {
         assert_eq!(InvalidToken, result.err().unwrap());
     }
 
+    #[test]
+    fn test_token_hash() {
+        let input = SessionToken::from_bytes([0u8; SESSION_TOKEN_LENGTH]);
+        let expected = "66687aadf862bd776c8fc18b8e9f8e20089714856ee233b3902a591d0d5f2925";
+
+        assert_eq!(expected, hex::encode(input.to_hash()));
+    }
+
+    #[test]
+    fn test_token_is_stored_hashed() {
+        let input = SessionToken::generate_new();
+
+        assert_ne!(input.0.expose_secret().to_vec(), input.to_database_value());
+        assert_eq!(input.to_hash(), input.to_database_value());
+    }
+
     #[test]
     fn test_token_fmt() {
         let input = SessionToken::from_bytes([
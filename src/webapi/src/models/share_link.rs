@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::execution::{Execution, Progress, State, TaskStatus};
+
+/// Link letting anyone who knows its token view an execution without an account.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ShareLink {
+    pub id: Uuid,
+    /// Username of the user who created the link.
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    /// Links without an expiry stay valid until revoked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Newly created link, together with its token; the token cannot be retrieved afterwards.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CreatedShareLink {
+    #[serde(flatten)]
+    pub link: ShareLink,
+    pub token: String,
+}
+
+/// Read-only view of an execution for people viewing it through a share link, leaving out who
+/// worked on it and what they entered.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SharedExecution {
+    pub title: String,
+    pub state: State,
+    pub progress: Progress,
+    pub tasks: Vec<SharedTask>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SharedTask {
+    pub title: String,
+    pub state: State,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SharedTask>,
+}
+
+impl SharedTask {
+    /// Lists visible tasks only, since hidden ones are not part of the execution's progress.
+    fn from_statuses(tasks: &[TaskStatus]) -> Vec<Self> {
+        tasks
+            .iter()
+            .filter(|t| t.visible)
+            .map(|t| SharedTask {
+                title: t.title.clone(),
+                state: t.state,
+                children: Self::from_statuses(&t.children),
+            })
+            .collect()
+    }
+}
+
+impl From<&Execution> for SharedExecution {
+    fn from(execution: &Execution) -> Self {
+        Self {
+            title: execution.title.clone(),
+            state: execution.state,
+            progress: execution.progress,
+            tasks: SharedTask::from_statuses(&execution.task_statuses),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::models::{checklist::Task, comment::CommentCounts};
+    use serde_json::json;
+
+    #[test]
+    fn test_shared_execution_leaves_out_hidden_tasks() {
+        let question = Uuid::new_v4();
+        let tasks: Vec<Task> = serde_json::from_value(json!([
+            {"id": question, "title": "Damaged?", "input": {"type": "boolean"}},
+            {"title": "Photograph damage", "visible_if": {"task_id": question, "equals": true}}
+        ]))
+        .unwrap();
+        let mut execution = Execution {
+            id: Uuid::new_v4(),
+            checklist_id: Uuid::new_v4(),
+            checklist_version: 1,
+            title: "Delivery".to_owned(),
+            variables: BTreeMap::new(),
+            batch_id: None,
+            target: None,
+            team_id: None,
+            task_statuses: tasks.iter().map(TaskStatus::from).collect(),
+            state: State::NotStarted,
+            reason: None,
+            progress: Progress::default(),
+            comments: CommentCounts::default(),
+        };
+        execution.refresh();
+
+        let shared = SharedExecution::from(&execution);
+
+        assert_eq!(1, shared.tasks.len());
+        assert_eq!("Damaged?", shared.tasks[0].title);
+        assert_eq!(1, shared.progress.total);
+    }
+}
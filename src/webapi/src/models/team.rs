@@ -0,0 +1,108 @@
+use std::{fmt, str::FromStr};
+
+use uuid::Uuid;
+
+/// Role of a user in a team, and so in checklists and executions of the team.
+///
+/// Each role allows everything the roles before it do.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    /// Can start executions and change states of their tasks.
+    Executor,
+    /// Can edit checklists.
+    Editor,
+    /// Can manage members of the team.
+    Owner,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Executor => "executor",
+            Self::Editor => "editor",
+            Self::Owner => "owner",
+        }
+    }
+
+    pub fn can_execute(&self) -> bool {
+        *self >= Self::Executor
+    }
+
+    pub fn can_edit(&self) -> bool {
+        *self >= Self::Editor
+    }
+
+    pub fn can_manage(&self) -> bool {
+        *self == Self::Owner
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[error("invalid role")]
+pub struct InvalidRole;
+
+impl FromStr for Role {
+    type Err = InvalidRole;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Self::Viewer),
+            "executor" => Ok(Self::Executor),
+            "editor" => Ok(Self::Editor),
+            "owner" => Ok(Self::Owner),
+            _ => Err(InvalidRole),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TeamMember {
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Team {
+    pub id: Uuid,
+    pub name: String,
+    pub members: Vec<TeamMember>,
+}
+
+/// Team the user is a member of, with their role in it.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TeamSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub role: Role,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in [Role::Viewer, Role::Executor, Role::Editor, Role::Owner] {
+            assert_eq!(Ok(role), Role::from_str(role.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_roles_include_lower_roles() {
+        assert!(!Role::Viewer.can_execute());
+        assert!(Role::Executor.can_execute());
+        assert!(!Role::Executor.can_edit());
+        assert!(Role::Owner.can_edit());
+        assert!(!Role::Editor.can_manage());
+    }
+}
@@ -0,0 +1,78 @@
+use std::{fs::OpenOptions, io::Write, path::PathBuf, sync::Arc, sync::Mutex};
+
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::json;
+
+use crate::configuration::NotifierSettings;
+
+/// Message for a user that is delivered outside of the API.
+pub enum Notification {
+    PasswordReset { token: SecretString },
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("failed to deliver notification: {0}")]
+pub struct NotifierError(String);
+
+/// Delivers notifications to users, e.g. by email.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, username: &str, notification: Notification) -> Result<(), NotifierError>;
+}
+
+fn to_json(username: &str, notification: &Notification) -> serde_json::Value {
+    match notification {
+        Notification::PasswordReset { token } => json!({
+            "username": username,
+            "kind": "password_reset",
+            "token": token.expose_secret(),
+        }),
+    }
+}
+
+/// Writes notifications to the application log, for local development.
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&self, username: &str, notification: Notification) -> Result<(), NotifierError> {
+        tracing::info!(
+            notification = %to_json(username, &notification),
+            "Notifying user"
+        );
+        Ok(())
+    }
+}
+
+/// Appends notifications to a file, one JSON object per line, for local development and tests.
+pub struct FileNotifier {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileNotifier {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl Notifier for FileNotifier {
+    fn notify(&self, username: &str, notification: Notification) -> Result<(), NotifierError> {
+        let _guard = self.lock.lock().map_err(|e| NotifierError(e.to_string()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| NotifierError(e.to_string()))?;
+        writeln!(file, "{}", to_json(username, &notification))
+            .map_err(|e| NotifierError(e.to_string()))
+    }
+}
+
+pub fn get_notifier(settings: &NotifierSettings) -> Arc<dyn Notifier> {
+    match settings {
+        NotifierSettings::Log => Arc::new(LogNotifier),
+        NotifierSettings::File { path } => Arc::new(FileNotifier::new(path)),
+    }
+}
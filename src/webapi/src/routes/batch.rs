@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use actix_web::{get, post, web, HttpResponse};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::{
+        batch_repository::{self, BatchRepositoryError},
+        execution_repository::ExecutionRepositoryError,
+    },
+    extractors::UserClaim,
+    models::checklist::VariableValue,
+    routes::permissions::{self, Permission},
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct StartBatchRequest {
+    /// Names of the targets to start an execution for, e.g. hostnames.
+    pub targets: Vec<String>,
+    /// Values of the checklist's variables, shared by all executions.
+    #[serde(default)]
+    pub variables: BTreeMap<String, VariableValue>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+pub struct StartBatchResponse {
+    pub batch_id: Uuid,
+}
+
+#[post("/checklists/{id}/batches")]
+#[tracing::instrument(
+    name = "Starting a batch of executions",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn start_batch(
+    path: web::Path<Uuid>,
+    request: web::Json<StartBatchRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_checklist(&pool, user_claim.user_id, *path, Permission::Execute)
+            .await
+    {
+        return response;
+    }
+    match batch_repository::start_batch(
+        &pool,
+        user_claim.user_id,
+        *path,
+        &request.targets,
+        &request.variables,
+    )
+    .await
+    {
+        Ok(batch_id) => HttpResponse::Ok().json(StartBatchResponse { batch_id }),
+        Err(BatchRepositoryError::Execution(e @ ExecutionRepositoryError::ChecklistNotFound)) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(
+            e @ (BatchRepositoryError::InvalidTargets { .. }
+            | BatchRepositoryError::Execution(
+                ExecutionRepositoryError::InvalidVariables { .. }
+                | ExecutionRepositoryError::InvalidChecklist { .. },
+            )),
+        ) => HttpResponse::UnprocessableEntity().json(json!({
+            "error": e.to_string()
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/batches/{id}")]
+#[tracing::instrument(
+    name = "Fetching a batch",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn get_batch(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match batch_repository::get_batch(&pool, user_claim.user_id, *path).await {
+        Ok(batch) => HttpResponse::Ok().json(batch),
+        Err(e @ BatchRepositoryError::BatchNotFound) => HttpResponse::NotFound().json(json!({
+            "error": e.to_string()
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
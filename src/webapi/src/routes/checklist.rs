@@ -0,0 +1,220 @@
+use actix_web::{get, post, put, web, HttpResponse};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::checklist_repository::{self, ChecklistRepositoryError},
+    extractors::UserClaim,
+    models::checklist::{Task, Variable},
+    routes::permissions::{self, Permission},
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ChecklistRequest {
+    pub title: String,
+    #[serde(default)]
+    pub variables: Vec<Variable>,
+    pub tasks: Vec<Task>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CreateChecklistRequest {
+    /// Team to share the checklist with; the creator needs to be at least its editor.
+    #[serde(default)]
+    pub team_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub checklist: ChecklistRequest,
+}
+
+#[get("/checklists")]
+#[tracing::instrument(
+    name = "Listing checklists",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_checklists(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match checklist_repository::list_checklists(&pool, user_claim.user_id).await {
+        Ok(checklists) => HttpResponse::Ok().json(checklists),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[post("/checklists")]
+#[tracing::instrument(
+    name = "Creating a checklist",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn create_checklist(
+    request: web::Json<CreateChecklistRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Some(team_id) = request.team_id {
+        if let Err(response) =
+            permissions::authorize_team(&pool, user_claim.user_id, team_id, Permission::Edit).await
+        {
+            return response;
+        }
+    }
+    match checklist_repository::insert_checklist(
+        &pool,
+        user_claim.user_id,
+        request.team_id,
+        &request.checklist,
+    )
+    .await
+    {
+        Ok(checklist) => HttpResponse::Ok().json(checklist),
+        Err(e @ ChecklistRepositoryError::InvalidChecklist { .. }) => {
+            HttpResponse::UnprocessableEntity().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/checklists/{id}")]
+#[tracing::instrument(
+    name = "Fetching a checklist",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn get_checklist(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match checklist_repository::get_checklist(&pool, user_claim.user_id, *path).await {
+        Ok(checklist) => HttpResponse::Ok().json(checklist),
+        Err(e @ ChecklistRepositoryError::ChecklistNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[put("/checklists/{id}")]
+#[tracing::instrument(
+    name = "Updating a checklist",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn update_checklist(
+    path: web::Path<Uuid>,
+    request: web::Json<ChecklistRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_checklist(&pool, user_claim.user_id, *path, Permission::Edit).await
+    {
+        return response;
+    }
+    match checklist_repository::update_checklist(&pool, user_claim.user_id, *path, &request).await {
+        Ok(checklist) => HttpResponse::Ok().json(checklist),
+        Err(e @ ChecklistRepositoryError::ChecklistNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(e @ ChecklistRepositoryError::InvalidChecklist { .. }) => {
+            HttpResponse::UnprocessableEntity().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/checklists/{id}/versions")]
+#[tracing::instrument(
+    name = "Listing versions of a checklist",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_checklist_versions(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match checklist_repository::list_checklist_versions(&pool, user_claim.user_id, *path).await {
+        Ok(versions) => HttpResponse::Ok().json(versions),
+        Err(e @ ChecklistRepositoryError::ChecklistNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/checklists/{id}/versions/{version}")]
+#[tracing::instrument(
+    name = "Fetching a version of a checklist",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn get_checklist_version(
+    path: web::Path<(Uuid, i32)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (checklist_id, version) = path.into_inner();
+    match checklist_repository::get_checklist_version(
+        &pool,
+        user_claim.user_id,
+        checklist_id,
+        version,
+    )
+    .await
+    {
+        Ok(checklist) => HttpResponse::Ok().json(checklist),
+        Err(e @ ChecklistRepositoryError::ChecklistNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[post("/checklists/{id}/versions/{version}/revert")]
+#[tracing::instrument(
+    name = "Reverting a checklist",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn revert_checklist(
+    path: web::Path<(Uuid, i32)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (checklist_id, version) = path.into_inner();
+    if let Err(response) =
+        permissions::authorize_checklist(&pool, user_claim.user_id, checklist_id, Permission::Edit)
+            .await
+    {
+        return response;
+    }
+    match checklist_repository::revert_checklist(&pool, user_claim.user_id, checklist_id, version)
+        .await
+    {
+        Ok(checklist) => HttpResponse::Ok().json(checklist),
+        Err(e @ ChecklistRepositoryError::ChecklistNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(e @ ChecklistRepositoryError::InvalidChecklist { .. }) => {
+            HttpResponse::UnprocessableEntity().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
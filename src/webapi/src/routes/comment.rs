@@ -0,0 +1,174 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::comment_repository::{self, CommentRepositoryError},
+    extractors::UserClaim,
+    models::execution::TaskRef,
+    routes::permissions::{self, Permission},
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CommentRequest {
+    pub body: String,
+}
+
+fn comment_error_response(e: CommentRepositoryError) -> HttpResponse {
+    let body = json!({
+        "error": e.to_string()
+    });
+    match e {
+        CommentRepositoryError::ExecutionNotFound
+        | CommentRepositoryError::TaskNotFound
+        | CommentRepositoryError::CommentNotFound => HttpResponse::NotFound().json(body),
+        CommentRepositoryError::NotAuthor => HttpResponse::Forbidden().json(body),
+        CommentRepositoryError::EmptyComment => HttpResponse::UnprocessableEntity().json(body),
+        CommentRepositoryError::InternalError => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+async fn add_comment(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    task: Option<TaskRef>,
+    body: &str,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_execution(pool, user_id, execution_id, Permission::Execute).await
+    {
+        return response;
+    }
+    match comment_repository::insert_comment(pool, user_id, execution_id, task, body).await {
+        Ok(comment) => HttpResponse::Ok().json(comment),
+        Err(e) => comment_error_response(e),
+    }
+}
+
+/// Lists comments on the whole execution together with comments on its tasks.
+#[get("/executions/{id}/comments")]
+#[tracing::instrument(
+    name = "Listing comments of an execution",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_comments(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match comment_repository::list_comments(&pool, user_claim.user_id, *path, None).await {
+        Ok(comments) => HttpResponse::Ok().json(comments),
+        Err(e) => comment_error_response(e),
+    }
+}
+
+#[get("/executions/{id}/tasks/{task}/comments")]
+#[tracing::instrument(
+    name = "Listing comments of an execution task",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_task_comments(
+    path: web::Path<(Uuid, TaskRef)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, task) = path.into_inner();
+    match comment_repository::list_comments(&pool, user_claim.user_id, execution_id, Some(task))
+        .await
+    {
+        Ok(comments) => HttpResponse::Ok().json(comments),
+        Err(e) => comment_error_response(e),
+    }
+}
+
+#[post("/executions/{id}/comments")]
+#[tracing::instrument(
+    name = "Commenting on an execution",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn create_comment(
+    path: web::Path<Uuid>,
+    request: web::Json<CommentRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    add_comment(&pool, user_claim.user_id, *path, None, &request.body).await
+}
+
+#[post("/executions/{id}/tasks/{task}/comments")]
+#[tracing::instrument(
+    name = "Commenting on an execution task",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn create_task_comment(
+    path: web::Path<(Uuid, TaskRef)>,
+    request: web::Json<CommentRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, task) = path.into_inner();
+    add_comment(
+        &pool,
+        user_claim.user_id,
+        execution_id,
+        Some(task),
+        &request.body,
+    )
+    .await
+}
+
+/// Only the author can edit a comment. Read access to the execution is enough, so authors can
+/// still correct their comments after losing the right to execute it.
+#[put("/executions/{id}/comments/{comment_id}")]
+#[tracing::instrument(
+    name = "Editing a comment",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn update_comment(
+    path: web::Path<(Uuid, Uuid)>,
+    request: web::Json<CommentRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, comment_id) = path.into_inner();
+    match comment_repository::update_comment(
+        &pool,
+        user_claim.user_id,
+        execution_id,
+        comment_id,
+        &request.body,
+    )
+    .await
+    {
+        Ok(comment) => HttpResponse::Ok().json(comment),
+        Err(e) => comment_error_response(e),
+    }
+}
+
+/// Only the author can delete a comment, which like editing requires only read access.
+#[delete("/executions/{id}/comments/{comment_id}")]
+#[tracing::instrument(
+    name = "Deleting a comment",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn delete_comment(
+    path: web::Path<(Uuid, Uuid)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, comment_id) = path.into_inner();
+    match comment_repository::delete_comment(&pool, user_claim.user_id, execution_id, comment_id)
+        .await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => comment_error_response(e),
+    }
+}
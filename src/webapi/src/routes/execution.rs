@@ -0,0 +1,328 @@
+use std::collections::BTreeMap;
+
+use actix_web::{get, post, put, web, HttpResponse};
+use secrecy::SecretString;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::execution_repository::{self, ExecutionRepositoryError},
+    extractors::UserClaim,
+    models::{
+        checklist::VariableValue,
+        execution::{Assignment, SignatureMeaning, State, TaskRef, TaskUpdate, TransitionError},
+    },
+    routes::permissions::{self, Permission},
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct UpdateStateRequest {
+    pub state: State,
+    pub reason: Option<String>,
+}
+
+/// Re-entered password of the user signing a change of state, and what they attest to.
+#[derive(serde::Deserialize)]
+pub struct SignatureRequest {
+    pub password: SecretString,
+    pub meaning: SignatureMeaning,
+}
+
+#[derive(serde::Deserialize)]
+pub struct UpdateTaskStateRequest {
+    #[serde(flatten)]
+    pub update: TaskUpdate,
+    #[serde(default)]
+    pub signature: Option<SignatureRequest>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct ExecuteChecklistRequest {
+    /// Values of the checklist's variables by their names.
+    #[serde(default)]
+    pub variables: BTreeMap<String, VariableValue>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+pub struct ExecuteChecklistResponse {
+    pub execution_id: Uuid,
+}
+
+/// The request body can be omitted for checklists without variables.
+#[post("/checklists/{id}/execute")]
+#[tracing::instrument(
+    name = "Executing a checklist",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn execute_checklist(
+    path: web::Path<Uuid>,
+    request: Option<web::Json<ExecuteChecklistRequest>>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_checklist(&pool, user_claim.user_id, *path, Permission::Execute)
+            .await
+    {
+        return response;
+    }
+    let request = request.map(web::Json::into_inner).unwrap_or_default();
+    match execution_repository::start_execution(
+        &pool,
+        user_claim.user_id,
+        *path,
+        &request.variables,
+    )
+    .await
+    {
+        Ok(execution_id) => HttpResponse::Ok().json(ExecuteChecklistResponse { execution_id }),
+        Err(e @ ExecutionRepositoryError::ChecklistNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(
+            e @ (ExecutionRepositoryError::InvalidVariables { .. }
+            | ExecutionRepositoryError::InvalidChecklist { .. }),
+        ) => HttpResponse::UnprocessableEntity().json(json!({
+            "error": e.to_string()
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/executions")]
+#[tracing::instrument(
+    name = "Listing executions",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_executions(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match execution_repository::list_executions(&pool, user_claim.user_id).await {
+        Ok(executions) => HttpResponse::Ok().json(executions),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/executions/{id}")]
+#[tracing::instrument(
+    name = "Fetching an execution",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn get_execution(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match execution_repository::get_execution(&pool, user_claim.user_id, *path).await {
+        Ok(execution) => HttpResponse::Ok().json(execution),
+        Err(e @ ExecutionRepositoryError::ExecutionNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+fn transition_error_response(e: &TransitionError) -> HttpResponse {
+    let mut body = serde_json::to_value(e).unwrap_or_default();
+    body["error"] = e.to_string().into();
+    match e {
+        TransitionError::TaskNotFound => HttpResponse::NotFound().json(body),
+        TransitionError::NotAssignee { .. } | TransitionError::SelfVerification => {
+            HttpResponse::Forbidden().json(body)
+        }
+        TransitionError::ReasonRequired
+        | TransitionError::ValueRequired
+        | TransitionError::InvalidValue { .. }
+        | TransitionError::SignatureRequired => HttpResponse::UnprocessableEntity().json(body),
+        _ => HttpResponse::Conflict().json(body),
+    }
+}
+
+#[put("/executions/{id}/state")]
+#[tracing::instrument(
+    name = "Updating state of an execution",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn update_execution_state(
+    path: web::Path<Uuid>,
+    request: web::Json<UpdateStateRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_execution(&pool, user_claim.user_id, *path, Permission::Execute)
+            .await
+    {
+        return response;
+    }
+    let request = request.into_inner();
+    match execution_repository::update_execution_state(
+        &pool,
+        user_claim.user_id,
+        *path,
+        request.state,
+        request.reason,
+    )
+    .await
+    {
+        Ok(execution) => HttpResponse::Ok().json(execution),
+        Err(e @ ExecutionRepositoryError::ExecutionNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(ExecutionRepositoryError::Transition(e)) => transition_error_response(&e),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Tasks are addressed by their id; a numeric position is accepted as a legacy alias.
+///
+/// Tasks assigned to a user, directly or through a section, can only be changed by them and by
+/// the user who started the execution, but not by other owners of its team. Tasks requiring a signature can only be changed with one.
+#[put("/executions/{id}/tasks/{task}/state")]
+#[tracing::instrument(
+    name = "Updating state of an execution task",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn update_task_state(
+    path: web::Path<(Uuid, TaskRef)>,
+    request: web::Json<UpdateTaskStateRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, task) = path.into_inner();
+    let UpdateTaskStateRequest { update, signature } = request.into_inner();
+    if let Err(response) = permissions::authorize_execution(
+        &pool,
+        user_claim.user_id,
+        execution_id,
+        Permission::Execute,
+    )
+    .await
+    {
+        return response;
+    }
+    // Only the owner of the execution can change tasks assigned to others
+    let is_owner =
+        match execution_repository::is_execution_owner(&pool, user_claim.user_id, execution_id)
+            .await
+        {
+            Ok(is_owner) => is_owner,
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        };
+    match execution_repository::update_task_state(
+        &pool,
+        user_claim.user_id,
+        execution_id,
+        task,
+        update,
+        signature,
+        is_owner,
+    )
+    .await
+    {
+        Ok(task_status) => HttpResponse::Ok().json(task_status),
+        Err(e @ ExecutionRepositoryError::ExecutionNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(e @ ExecutionRepositoryError::InvalidPassword) => {
+            HttpResponse::Forbidden().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(ExecutionRepositoryError::Transition(e)) => transition_error_response(&e),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Verification has to be recorded by a different user than the one who completed the task.
+#[post("/executions/{id}/tasks/{task}/verify")]
+#[tracing::instrument(
+    name = "Verifying an execution task",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn verify_task(
+    path: web::Path<(Uuid, TaskRef)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, task) = path.into_inner();
+    if let Err(response) = permissions::authorize_execution(
+        &pool,
+        user_claim.user_id,
+        execution_id,
+        Permission::Execute,
+    )
+    .await
+    {
+        return response;
+    }
+    match execution_repository::verify_task(&pool, user_claim.user_id, execution_id, task).await {
+        Ok(task_status) => HttpResponse::Ok().json(task_status),
+        Err(e @ ExecutionRepositoryError::ExecutionNotFound) => {
+            HttpResponse::NotFound().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(ExecutionRepositoryError::Transition(e)) => transition_error_response(&e),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Sending no assignee unassigns the task.
+#[put("/executions/{id}/tasks/{task}/assignee")]
+#[tracing::instrument(
+    name = "Assigning an execution task",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn assign_task(
+    path: web::Path<(Uuid, TaskRef)>,
+    request: web::Json<Assignment>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, task) = path.into_inner();
+    if let Err(response) =
+        permissions::authorize_execution(&pool, user_claim.user_id, execution_id, Permission::Edit)
+            .await
+    {
+        return response;
+    }
+    match execution_repository::assign_task(
+        &pool,
+        user_claim.user_id,
+        execution_id,
+        task,
+        request.into_inner(),
+    )
+    .await
+    {
+        Ok(task_status) => HttpResponse::Ok().json(task_status),
+        Err(
+            e @ (ExecutionRepositoryError::ExecutionNotFound
+            | ExecutionRepositoryError::UserNotFound { .. }),
+        ) => HttpResponse::NotFound().json(json!({
+            "error": e.to_string()
+        })),
+        Err(e @ ExecutionRepositoryError::InvalidAssignee { .. }) => {
+            HttpResponse::UnprocessableEntity().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(ExecutionRepositoryError::Transition(e)) => transition_error_response(&e),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
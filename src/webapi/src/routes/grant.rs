@@ -0,0 +1,109 @@
+use actix_web::{delete, get, put, web, HttpResponse};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::grant_repository::{self, GrantRepositoryError},
+    extractors::UserClaim,
+    models::grant::Access,
+    routes::permissions::{self, Permission},
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct GrantRequest {
+    pub access: Access,
+}
+
+fn grant_error_response(e: GrantRepositoryError) -> HttpResponse {
+    let body = json!({
+        "error": e.to_string()
+    });
+    match e {
+        GrantRepositoryError::UserNotFound { .. } | GrantRepositoryError::GrantNotFound { .. } => {
+            HttpResponse::NotFound().json(body)
+        }
+        GrantRepositoryError::OwnerGrant => HttpResponse::UnprocessableEntity().json(body),
+        GrantRepositoryError::InternalError => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/checklists/{id}/grants")]
+#[tracing::instrument(
+    name = "Listing grants of a checklist",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_grants(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_checklist(&pool, user_claim.user_id, *path, Permission::Manage).await
+    {
+        return response;
+    }
+    match grant_repository::list_grants(&pool, *path).await {
+        Ok(grants) => HttpResponse::Ok().json(grants),
+        Err(e) => grant_error_response(e),
+    }
+}
+
+/// Replaces access granted to the user before, if any.
+#[put("/checklists/{id}/grants/{username}")]
+#[tracing::instrument(
+    name = "Granting access to a checklist",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn set_grant(
+    path: web::Path<(Uuid, String)>,
+    request: web::Json<GrantRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (checklist_id, username) = path.into_inner();
+    if let Err(response) = permissions::authorize_checklist(
+        &pool,
+        user_claim.user_id,
+        checklist_id,
+        Permission::Manage,
+    )
+    .await
+    {
+        return response;
+    }
+    match grant_repository::set_grant(&pool, checklist_id, &username, request.access).await {
+        Ok(grant) => HttpResponse::Ok().json(grant),
+        Err(e) => grant_error_response(e),
+    }
+}
+
+#[delete("/checklists/{id}/grants/{username}")]
+#[tracing::instrument(
+    name = "Revoking access to a checklist",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn revoke_grant(
+    path: web::Path<(Uuid, String)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (checklist_id, username) = path.into_inner();
+    if let Err(response) = permissions::authorize_checklist(
+        &pool,
+        user_claim.user_id,
+        checklist_id,
+        Permission::Manage,
+    )
+    .await
+    {
+        return response;
+    }
+    match grant_repository::revoke_grant(&pool, checklist_id, &username).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => grant_error_response(e),
+    }
+}
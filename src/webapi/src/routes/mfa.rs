@@ -0,0 +1,128 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    controller::mfa_repository::{self, MfaRepositoryError},
+    extractors::UserClaim,
+};
+
+#[derive(serde::Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+/// Password of the user, checked again before changing their second factors.
+#[derive(serde::Deserialize)]
+pub struct PasswordRequest {
+    pub password: SecretString,
+}
+
+fn mfa_error_response(e: MfaRepositoryError) -> HttpResponse {
+    let body = json!({
+        "error": e.to_string()
+    });
+    match e {
+        MfaRepositoryError::AlreadyEnabled => HttpResponse::Conflict().json(body),
+        MfaRepositoryError::NotEnrolled | MfaRepositoryError::NotEnabled => {
+            HttpResponse::NotFound().json(body)
+        }
+        MfaRepositoryError::InvalidCode => HttpResponse::UnprocessableEntity().json(body),
+        MfaRepositoryError::InvalidPassword => HttpResponse::Forbidden().json(body),
+        MfaRepositoryError::Locked => HttpResponse::TooManyRequests().json(body),
+        MfaRepositoryError::InternalError => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+fn recovery_codes_response(codes: &[SecretString]) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "recovery_codes": codes.iter().map(|c| c.expose_secret()).collect::<Vec<_>>()
+    }))
+}
+
+#[get("/user/mfa")]
+#[tracing::instrument(
+    name = "Fetching MFA status of a user",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn get_mfa_status(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match mfa_repository::get_mfa_status(&pool, user_claim.user_id).await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => mfa_error_response(e),
+    }
+}
+
+/// Provisions a new authenticator; it is only required at login once confirmed with a code.
+#[post("/user/mfa/totp")]
+#[tracing::instrument(
+    name = "Enrolling TOTP authenticator of a user",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn enrol_totp(
+    request: web::Json<PasswordRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match mfa_repository::start_totp_enrolment(&pool, user_claim.user_id, &request.password).await {
+        Ok(enrolment) => HttpResponse::Ok().json(enrolment),
+        Err(e) => mfa_error_response(e),
+    }
+}
+
+/// Enables the authenticator and returns recovery codes, which are not shown again.
+#[post("/user/mfa/totp/confirm")]
+#[tracing::instrument(
+    name = "Confirming TOTP authenticator of a user",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn confirm_totp(
+    request: web::Json<ConfirmTotpRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match mfa_repository::confirm_totp_enrolment(&pool, user_claim.user_id, &request.code).await {
+        Ok(codes) => recovery_codes_response(&codes),
+        Err(e) => mfa_error_response(e),
+    }
+}
+
+#[delete("/user/mfa/totp")]
+#[tracing::instrument(
+    name = "Disabling TOTP authenticator of a user",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn disable_totp(
+    request: web::Json<PasswordRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match mfa_repository::disable_totp(&pool, user_claim.user_id, &request.password).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => mfa_error_response(e),
+    }
+}
+
+/// Replaces all recovery codes of the user with new ones.
+#[post("/user/mfa/recovery-codes")]
+#[tracing::instrument(
+    name = "Regenerating recovery codes of a user",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn regenerate_recovery_codes(
+    request: web::Json<PasswordRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match mfa_repository::regenerate_recovery_codes(&pool, user_claim.user_id, &request.password)
+        .await
+    {
+        Ok(codes) => recovery_codes_response(&codes),
+        Err(e) => mfa_error_response(e),
+    }
+}
@@ -1,2 +1,11 @@
+pub mod batch;
+pub mod checklist;
+pub mod comment;
+pub mod execution;
+pub mod grant;
 pub(crate) mod infra;
+pub mod mfa;
+pub(crate) mod permissions;
+pub mod share_link;
+pub mod team;
 pub mod user;
@@ -0,0 +1,96 @@
+use actix_web::HttpResponse;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::{
+        checklist_repository::{self, ChecklistRepositoryError},
+        execution_repository::{self, ExecutionRepositoryError},
+        team_repository::{self, TeamRepositoryError},
+    },
+    models::team::Role,
+};
+
+/// Action a user wants to perform; reading is allowed by every role, and is enforced by
+/// fetching only what users have access to.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Permission {
+    Execute,
+    Edit,
+    /// Managing members of a team, or who a checklist is shared with.
+    Manage,
+}
+
+impl Permission {
+    fn is_allowed_for(&self, role: Role) -> bool {
+        match self {
+            Self::Execute => role.can_execute(),
+            Self::Edit => role.can_edit(),
+            Self::Manage => role.can_manage(),
+        }
+    }
+}
+
+/// Response for users whose role does not allow what they requested.
+fn forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(json!({
+        "error": "insufficient permissions"
+    }))
+}
+
+fn not_found(e: impl ToString) -> HttpResponse {
+    HttpResponse::NotFound().json(json!({
+        "error": e.to_string()
+    }))
+}
+
+fn check(role: Role, permission: Permission) -> Result<Role, HttpResponse> {
+    if permission.is_allowed_for(role) {
+        Ok(role)
+    } else {
+        Err(forbidden())
+    }
+}
+
+/// Checks and returns the user's role in a checklist, through ownership, its team or a grant.
+pub(crate) async fn authorize_checklist(
+    pool: &PgPool,
+    user_id: Uuid,
+    checklist_id: Uuid,
+    permission: Permission,
+) -> Result<Role, HttpResponse> {
+    match checklist_repository::get_checklist_role(pool, user_id, checklist_id).await {
+        Ok(role) => check(role, permission),
+        Err(e @ ChecklistRepositoryError::ChecklistNotFound) => Err(not_found(e)),
+        Err(_) => Err(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+/// Checks and returns the user's role in an execution, through ownership or its team.
+pub(crate) async fn authorize_execution(
+    pool: &PgPool,
+    user_id: Uuid,
+    execution_id: Uuid,
+    permission: Permission,
+) -> Result<Role, HttpResponse> {
+    match execution_repository::get_execution_role(pool, user_id, execution_id).await {
+        Ok(role) => check(role, permission),
+        Err(e @ ExecutionRepositoryError::ExecutionNotFound) => Err(not_found(e)),
+        Err(_) => Err(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+/// Checks and returns the user's role in a team; teams are not found for non-members.
+pub(crate) async fn authorize_team(
+    pool: &PgPool,
+    user_id: Uuid,
+    team_id: Uuid,
+    permission: Permission,
+) -> Result<Role, HttpResponse> {
+    match team_repository::get_team_role(pool, user_id, team_id).await {
+        Ok(role) => check(role, permission),
+        Err(e @ TeamRepositoryError::TeamNotFound) => Err(not_found(e)),
+        Err(_) => Err(HttpResponse::InternalServerError().finish()),
+    }
+}
@@ -0,0 +1,134 @@
+use std::str::FromStr;
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::{
+        execution_repository::{self, ExecutionRepositoryError},
+        share_link_repository::{self, ShareLinkRepositoryError},
+    },
+    extractors::UserClaim,
+    models::{session_token::SessionToken, share_link::SharedExecution},
+    routes::permissions::{self, Permission},
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct CreateShareLinkRequest {
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+fn share_link_error_response(e: ShareLinkRepositoryError) -> HttpResponse {
+    let body = json!({
+        "error": e.to_string()
+    });
+    match e {
+        ShareLinkRepositoryError::ShareLinkNotFound => HttpResponse::NotFound().json(body),
+        ShareLinkRepositoryError::InvalidExpiry => HttpResponse::UnprocessableEntity().json(body),
+        ShareLinkRepositoryError::InternalError => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// The request body can be omitted for links that do not expire.
+#[post("/executions/{id}/share-links")]
+#[tracing::instrument(
+    name = "Creating a share link",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn create_share_link(
+    path: web::Path<Uuid>,
+    request: Option<web::Json<CreateShareLinkRequest>>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_execution(&pool, user_claim.user_id, *path, Permission::Edit).await
+    {
+        return response;
+    }
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+    match share_link_repository::insert_share_link(
+        &pool,
+        user_claim.user_id,
+        *path,
+        request.expires_at,
+    )
+    .await
+    {
+        Ok(link) => HttpResponse::Ok().json(link),
+        Err(e) => share_link_error_response(e),
+    }
+}
+
+#[get("/executions/{id}/share-links")]
+#[tracing::instrument(
+    name = "Listing share links of an execution",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_share_links(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    if let Err(response) =
+        permissions::authorize_execution(&pool, user_claim.user_id, *path, Permission::Edit).await
+    {
+        return response;
+    }
+    match share_link_repository::list_share_links(&pool, *path).await {
+        Ok(links) => HttpResponse::Ok().json(links),
+        Err(e) => share_link_error_response(e),
+    }
+}
+
+#[delete("/executions/{id}/share-links/{link_id}")]
+#[tracing::instrument(
+    name = "Revoking a share link",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn revoke_share_link(
+    path: web::Path<(Uuid, Uuid)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (execution_id, link_id) = path.into_inner();
+    if let Err(response) =
+        permissions::authorize_execution(&pool, user_claim.user_id, execution_id, Permission::Edit)
+            .await
+    {
+        return response;
+    }
+    match share_link_repository::revoke_share_link(&pool, execution_id, link_id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => share_link_error_response(e),
+    }
+}
+
+/// Available without logging in; revoked and expired links are not found.
+#[get("/shared/{token}")]
+#[tracing::instrument(name = "Viewing a shared execution", skip(path, pool))]
+pub async fn get_shared_execution(
+    path: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let not_found = || {
+        HttpResponse::NotFound().json(json!({
+            "error": ShareLinkRepositoryError::ShareLinkNotFound.to_string()
+        }))
+    };
+    let Ok(token) = SessionToken::from_str(&path) else {
+        return not_found();
+    };
+    match execution_repository::get_shared_execution(&pool, &token.to_hash()).await {
+        Ok(execution) => HttpResponse::Ok().json(SharedExecution::from(&execution)),
+        Err(ExecutionRepositoryError::ExecutionNotFound) => not_found(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -0,0 +1,133 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    controller::team_repository::{self, TeamRepositoryError},
+    extractors::UserClaim,
+    models::team::Role,
+    routes::permissions::{self, Permission},
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CreateTeamRequest {
+    pub name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct SetMemberRoleRequest {
+    pub role: Role,
+}
+
+fn team_error_response(e: TeamRepositoryError) -> HttpResponse {
+    let body = json!({
+        "error": e.to_string()
+    });
+    match e {
+        TeamRepositoryError::TeamNotFound
+        | TeamRepositoryError::UserNotFound { .. }
+        | TeamRepositoryError::MemberNotFound { .. } => HttpResponse::NotFound().json(body),
+        TeamRepositoryError::InvalidTeam { .. } => HttpResponse::UnprocessableEntity().json(body),
+        TeamRepositoryError::LastOwner => HttpResponse::Conflict().json(body),
+        TeamRepositoryError::InternalError => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[post("/teams")]
+#[tracing::instrument(
+    name = "Creating a team",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn create_team(
+    request: web::Json<CreateTeamRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match team_repository::insert_team(&pool, user_claim.user_id, &request.name).await {
+        Ok(team) => HttpResponse::Ok().json(team),
+        Err(e) => team_error_response(e),
+    }
+}
+
+#[get("/teams")]
+#[tracing::instrument(
+    name = "Listing teams",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_teams(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match team_repository::list_teams(&pool, user_claim.user_id).await {
+        Ok(teams) => HttpResponse::Ok().json(teams),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/teams/{id}")]
+#[tracing::instrument(
+    name = "Fetching a team",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn get_team(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match team_repository::get_team(&pool, user_claim.user_id, *path).await {
+        Ok(team) => HttpResponse::Ok().json(team),
+        Err(e) => team_error_response(e),
+    }
+}
+
+/// Adds the user to the team if they are not a member yet.
+#[put("/teams/{id}/members/{username}")]
+#[tracing::instrument(
+    name = "Setting role of a team member",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn set_member_role(
+    path: web::Path<(Uuid, String)>,
+    request: web::Json<SetMemberRoleRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (team_id, username) = path.into_inner();
+    if let Err(response) =
+        permissions::authorize_team(&pool, user_claim.user_id, team_id, Permission::Manage).await
+    {
+        return response;
+    }
+    match team_repository::set_member_role(&pool, team_id, &username, request.role).await {
+        Ok(()) => match team_repository::get_team(&pool, user_claim.user_id, team_id).await {
+            Ok(team) => HttpResponse::Ok().json(team),
+            Err(e) => team_error_response(e),
+        },
+        Err(e) => team_error_response(e),
+    }
+}
+
+#[delete("/teams/{id}/members/{username}")]
+#[tracing::instrument(
+    name = "Removing a team member",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn remove_member(
+    path: web::Path<(Uuid, String)>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let (team_id, username) = path.into_inner();
+    if let Err(response) =
+        permissions::authorize_team(&pool, user_claim.user_id, team_id, Permission::Manage).await
+    {
+        return response;
+    }
+    match team_repository::remove_member(&pool, team_id, &username).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => team_error_response(e),
+    }
+}
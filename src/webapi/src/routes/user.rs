@@ -1,11 +1,24 @@
-use actix_web::{get, post, web, HttpResponse};
+use std::str::FromStr;
+
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse};
 use secrecy::{ExposeSecret, SecretString};
 use serde_json::json;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{
-    controller::user_repository::{self, UserRepositoryError},
+    configuration::SessionSettings,
+    controller::{
+        execution_repository,
+        user_repository::{self, UserRepositoryError},
+    },
     extractors::UserClaim,
+    models::{
+        mfa::SecondFactor,
+        session::{ClientInfo, LoginOutcome, SessionTokens},
+        session_token::SessionToken,
+    },
+    notifier::{Notification, Notifier},
 };
 
 #[derive(serde::Deserialize)]
@@ -59,13 +72,278 @@ pub struct LoginUserRequest {
 )]
 pub async fn login_user(
     request: web::Json<LoginUserRequest>,
+    http_request: HttpRequest,
     pool: web::Data<PgPool>,
+    settings: web::Data<SessionSettings>,
 ) -> HttpResponse {
-    match user_repository::login_user(&pool, &request).await {
-        Ok(token) => HttpResponse::Ok().json(json!( {
-            "token": token.to_secret_string().expose_secret(),
+    let client = client_info(&http_request, &settings);
+    match user_repository::login_user(&pool, &request, &client, &settings).await {
+        Ok(LoginOutcome::Authenticated(tokens)) => tokens_response(&tokens),
+        Ok(LoginOutcome::MfaRequired {
+            challenge,
+            expires_at,
+        }) => HttpResponse::Ok().json(json!({
+            "mfa_required": true,
+            "challenge": challenge.to_secret_string().expose_secret(),
+            "expires_at": expires_at,
         })),
         Err(UserRepositoryError::InvalidUserOrPassword) => HttpResponse::Unauthorized().finish(),
+        Err(e @ UserRepositoryError::MfaLocked) => HttpResponse::TooManyRequests().json(json!({
+            "error": e.to_string()
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+fn client_info(http_request: &HttpRequest, settings: &SessionSettings) -> ClientInfo {
+    let ip = if settings.trust_forwarded_headers {
+        http_request
+            .connection_info()
+            .realip_remote_addr()
+            .map(|ip| ip.to_owned())
+    } else {
+        http_request.peer_addr().map(|addr| addr.ip().to_string())
+    };
+    ClientInfo {
+        user_agent: http_request
+            .headers()
+            .get("User-Agent")
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.to_owned()),
+        ip,
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct MfaLoginRequest {
+    pub challenge: SecretString,
+    #[serde(flatten)]
+    pub factor: SecondFactor,
+}
+
+/// Completes the login of a user with a second factor, using the challenge returned when they
+/// logged in with their password.
+#[post("/user/login/mfa")]
+#[tracing::instrument(
+    name = "Completing login of a user",
+    skip(request, http_request, pool, settings)
+)]
+pub async fn complete_mfa_login(
+    request: web::Json<MfaLoginRequest>,
+    http_request: HttpRequest,
+    pool: web::Data<PgPool>,
+    settings: web::Data<SessionSettings>,
+) -> HttpResponse {
+    let Ok(challenge) = SessionToken::from_str(request.challenge.expose_secret()) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    let client = client_info(&http_request, &settings);
+    match user_repository::complete_mfa_login(&pool, challenge, &request.factor, &client, &settings)
+        .await
+    {
+        Ok(tokens) => tokens_response(&tokens),
+        Err(
+            e @ (UserRepositoryError::InvalidMfaChallenge | UserRepositoryError::InvalidMfaCode),
+        ) => HttpResponse::Unauthorized().json(json!({
+            "error": e.to_string()
+        })),
+        Err(e @ UserRepositoryError::MfaLocked) => HttpResponse::TooManyRequests().json(json!({
+            "error": e.to_string()
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+fn tokens_response(tokens: &SessionTokens) -> HttpResponse {
+    HttpResponse::Ok().json(json!( {
+        "token": tokens.token.to_secret_string().expose_secret(),
+        "refresh_token": tokens.refresh_token.to_secret_string().expose_secret(),
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct RefreshSessionRequest {
+    pub refresh_token: SecretString,
+}
+
+/// Replaces both tokens of a session; the refresh token cannot be used again.
+#[post("/user/refresh")]
+#[tracing::instrument(name = "Refreshing a session", skip(request, pool, settings))]
+pub async fn refresh_session(
+    request: web::Json<RefreshSessionRequest>,
+    pool: web::Data<PgPool>,
+    settings: web::Data<SessionSettings>,
+) -> HttpResponse {
+    let Ok(refresh_token) = SessionToken::from_str(request.refresh_token.expose_secret()) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    match user_repository::refresh_session(&pool, refresh_token, &settings).await {
+        Ok(tokens) => tokens_response(&tokens),
+        Err(UserRepositoryError::SessionNotFound) => HttpResponse::Unauthorized().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: SecretString,
+    pub new_password: SecretString,
+}
+
+/// Changes the password and logs the user out everywhere except for the current session.
+#[put("/user/password")]
+#[tracing::instrument(
+    name = "Changing password of a user",
+    skip(request, user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn change_password(
+    request: web::Json<ChangePasswordRequest>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match user_repository::change_password(
+        &pool,
+        user_claim.user_id,
+        user_claim.session_id,
+        &request.current_password,
+        &request.new_password,
+    )
+    .await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e @ UserRepositoryError::InvalidUserOrPassword) => {
+            HttpResponse::Forbidden().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PasswordResetRequest {
+    pub username: String,
+}
+
+/// Sends a reset token to the user through the notifier. Responds the same way whether the user
+/// exists or not, so that it cannot be used to find out usernames.
+#[post("/user/password-reset")]
+#[tracing::instrument(
+    name = "Requesting a password reset",
+    skip(request, pool, notifier),
+    fields(
+        username = %request.username
+    )
+)]
+pub async fn request_password_reset(
+    request: web::Json<PasswordResetRequest>,
+    pool: web::Data<PgPool>,
+    notifier: web::Data<dyn Notifier>,
+) -> HttpResponse {
+    match user_repository::create_password_reset(&pool, &request.username).await {
+        Ok(Some(token)) => {
+            let notification = Notification::PasswordReset {
+                token: token.to_secret_string(),
+            };
+            match notifier.notify(&request.username, notification) {
+                Ok(()) => HttpResponse::Accepted().finish(),
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    HttpResponse::InternalServerError().finish()
+                }
+            }
+        }
+        Ok(None) => HttpResponse::Accepted().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: SecretString,
+    pub new_password: SecretString,
+}
+
+/// Sets a new password using a reset token and logs the user out everywhere.
+#[post("/user/password-reset/confirm")]
+#[tracing::instrument(name = "Resetting password of a user", skip(request, pool))]
+pub async fn reset_password(
+    request: web::Json<ResetPasswordRequest>,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    let Ok(token) = SessionToken::from_str(request.token.expose_secret()) else {
+        return HttpResponse::Unauthorized().finish();
+    };
+    match user_repository::reset_password(&pool, token, &request.new_password).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e @ UserRepositoryError::InvalidResetToken) => {
+            HttpResponse::Unauthorized().json(json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[post("/user/logout")]
+#[tracing::instrument(
+    name = "Logging out a user",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn logout_user(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match user_repository::delete_session(&pool, user_claim.user_id, user_claim.session_id).await {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/user/sessions")]
+#[tracing::instrument(
+    name = "Listing sessions of a user",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_sessions(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match user_repository::list_sessions(&pool, user_claim.user_id, user_claim.session_id).await {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Logs the user out everywhere else, keeping only the current session.
+#[delete("/user/sessions")]
+#[tracing::instrument(
+    name = "Deleting other sessions of a user",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn delete_other_sessions(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match user_repository::delete_other_sessions(&pool, user_claim.user_id, user_claim.session_id)
+        .await
+    {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[delete("/user/sessions/{id}")]
+#[tracing::instrument(
+    name = "Deleting a session of a user",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn delete_session(
+    path: web::Path<Uuid>,
+    user_claim: UserClaim,
+    pool: web::Data<PgPool>,
+) -> HttpResponse {
+    match user_repository::delete_session(&pool, user_claim.user_id, *path).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e @ UserRepositoryError::SessionNotFound) => HttpResponse::NotFound().json(json!({
+            "error": e.to_string()
+        })),
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
@@ -81,3 +359,16 @@ pub async fn get_current_user(user_claim: UserClaim, pool: web::Data<PgPool>) ->
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
+
+#[get("/user/tasks")]
+#[tracing::instrument(
+    name = "Listing tasks assigned to the user",
+    skip(user_claim, pool),
+    fields(user_id = %user_claim.user_id)
+)]
+pub async fn list_assigned_tasks(user_claim: UserClaim, pool: web::Data<PgPool>) -> HttpResponse {
+    match execution_repository::list_assigned_tasks(&pool, user_claim.user_id).await {
+        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -1,20 +1,79 @@
-use crate::routes;
-use actix_web::web::Data;
-use actix_web::{dev::Server, App, HttpServer};
+use crate::{configuration::SessionSettings, notifier::Notifier, routes};
+use actix_web::web::{Data, PathConfig};
+use actix_web::{dev::Server, error, App, HttpServer};
 use sqlx::PgPool;
-use std::net::TcpListener;
+use std::{net::TcpListener, sync::Arc};
 use tracing_actix_web::TracingLogger;
 
-pub fn run(listener: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Error> {
+pub fn run(
+    listener: TcpListener,
+    db_pool: PgPool,
+    session_settings: SessionSettings,
+    notifier: Arc<dyn Notifier>,
+) -> Result<Server, std::io::Error> {
     let db_pool = Data::new(db_pool);
+    let session_settings = Data::new(session_settings);
+    let notifier: Data<dyn Notifier> = Data::from(notifier);
     let server = HttpServer::new(move || {
         App::new()
             .wrap(TracingLogger::default())
             .service(routes::infra::ping)
             .service(routes::user::create_user)
             .service(routes::user::login_user)
+            .service(routes::user::complete_mfa_login)
+            .service(routes::user::refresh_session)
+            .service(routes::user::change_password)
+            .service(routes::user::request_password_reset)
+            .service(routes::user::reset_password)
             .service(routes::user::get_current_user)
+            .service(routes::user::list_assigned_tasks)
+            .service(routes::user::logout_user)
+            .service(routes::user::list_sessions)
+            .service(routes::user::delete_other_sessions)
+            .service(routes::user::delete_session)
+            .service(routes::mfa::get_mfa_status)
+            .service(routes::mfa::enrol_totp)
+            .service(routes::mfa::confirm_totp)
+            .service(routes::mfa::disable_totp)
+            .service(routes::mfa::regenerate_recovery_codes)
+            .service(routes::checklist::list_checklists)
+            .service(routes::checklist::create_checklist)
+            .service(routes::checklist::get_checklist)
+            .service(routes::checklist::update_checklist)
+            .service(routes::checklist::list_checklist_versions)
+            .service(routes::checklist::get_checklist_version)
+            .service(routes::checklist::revert_checklist)
+            .service(routes::grant::list_grants)
+            .service(routes::grant::set_grant)
+            .service(routes::grant::revoke_grant)
+            .service(routes::execution::execute_checklist)
+            .service(routes::execution::list_executions)
+            .service(routes::execution::get_execution)
+            .service(routes::execution::update_execution_state)
+            .service(routes::execution::update_task_state)
+            .service(routes::execution::assign_task)
+            .service(routes::execution::verify_task)
+            .service(routes::comment::list_comments)
+            .service(routes::comment::list_task_comments)
+            .service(routes::comment::create_comment)
+            .service(routes::comment::create_task_comment)
+            .service(routes::comment::update_comment)
+            .service(routes::comment::delete_comment)
+            .service(routes::share_link::create_share_link)
+            .service(routes::share_link::list_share_links)
+            .service(routes::share_link::revoke_share_link)
+            .service(routes::share_link::get_shared_execution)
+            .service(routes::batch::start_batch)
+            .service(routes::batch::get_batch)
+            .service(routes::team::create_team)
+            .service(routes::team::list_teams)
+            .service(routes::team::get_team)
+            .service(routes::team::set_member_role)
+            .service(routes::team::remove_member)
             .app_data(db_pool.clone())
+            .app_data(session_settings.clone())
+            .app_data(notifier.clone())
+            .app_data(PathConfig::default().error_handler(|e, _| error::ErrorBadRequest(e)))
     })
     .listen(listener)?
     .run();
@@ -0,0 +1,165 @@
+mod common;
+
+use serde_json::json;
+use webapi::{
+    models::{batch::Batch, execution::State},
+    routes::batch::StartBatchResponse,
+};
+
+#[tokio::test]
+async fn batch_rolls_up_progress_of_its_executions() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Store opening",
+            "tasks": [{"title": "Unlock doors"}, {"title": "Start tills"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = checklist["id"].as_str().unwrap();
+
+    // Act
+    let response = client
+        .post(format!(
+            "{}/checklists/{}/batches",
+            &app.address, checklist_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"targets": ["store-1", "store-2", "store-3"]}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let batch_id = response
+        .json::<StartBatchResponse>()
+        .await
+        .unwrap()
+        .batch_id;
+    let executions = client
+        .get(format!("{}/executions", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    for execution in executions.iter().take(2) {
+        client
+            .put(format!(
+                "{}/executions/{}/tasks/0/state",
+                &app.address,
+                execution["id"].as_str().unwrap()
+            ))
+            .bearer_auth(&token)
+            .json(&json!({"state": "done"}))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+    }
+    let batch = client
+        .get(format!("{}/batches/{}", &app.address, batch_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Batch>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(3, batch.executions);
+    assert_eq!(2, batch.progress.completed);
+    assert_eq!(6, batch.progress.total);
+    assert_eq!(Some(&2), batch.states.get(&State::InProgress));
+    assert_eq!(Some(&1), batch.states.get(&State::NotStarted));
+    assert_eq!(1, batch.lagging.len());
+    assert_eq!(State::NotStarted, batch.lagging[0].state);
+}
+
+#[tokio::test]
+async fn start_batch_returns_422_for_duplicate_targets() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"title": "Store opening", "tasks": [{"title": "Unlock doors"}]}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+
+    // Act
+    let response = client
+        .post(format!(
+            "{}/checklists/{}/batches",
+            &app.address,
+            checklist["id"].as_str().unwrap()
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"targets": ["store-1", "store-1"]}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_batch_returns_404_for_batch_of_other_user() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"title": "Store opening", "tasks": [{"title": "Unlock doors"}]}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let batch_id = client
+        .post(format!(
+            "{}/checklists/{}/batches",
+            &app.address,
+            checklist["id"].as_str().unwrap()
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"targets": ["store-1"]}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<StartBatchResponse>()
+        .await
+        .unwrap()
+        .batch_id;
+
+    // Act
+    let response = client
+        .get(format!("{}/batches/{}", &app.address, batch_id))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
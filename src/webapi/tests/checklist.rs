@@ -0,0 +1,505 @@
+mod common;
+
+use serde_json::json;
+use webapi::models::checklist::{Checklist, ChecklistSummary, ChecklistVersionSummary};
+
+#[tokio::test]
+async fn creating_checklist_returns_it_with_owner() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [{"title": "Check fuel"}, {"title": "Check tyres"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let created = response.json::<Checklist>().await.unwrap();
+    assert_eq!(created.title, "Pre-flight");
+    assert_eq!(created.owner, "jozin");
+    assert_eq!(
+        vec!["Check fuel", "Check tyres"],
+        created
+            .tasks
+            .iter()
+            .map(|t| t.title.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    let fetched = client
+        .get(format!("{}/checklists/{}", &app.address, created.id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, fetched.status().as_u16());
+    assert_eq!(created, fetched.json::<Checklist>().await.unwrap());
+}
+
+#[tokio::test]
+async fn listing_checklists_returns_only_own_checklists() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let jozin = app.create_logged_in_user("jozin").await;
+    let krtek = app.create_logged_in_user("krtek").await;
+
+    for (token, title) in [(&jozin, "Jozin's"), (&krtek, "Krtek's")] {
+        client
+            .post(format!("{}/checklists", &app.address))
+            .bearer_auth(token)
+            .json(&json!({"title": title, "tasks": []}))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+    }
+
+    // Act
+    let response = client
+        .get(format!("{}/checklists", &app.address))
+        .bearer_auth(&jozin)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let checklists = response.json::<Vec<ChecklistSummary>>().await.unwrap();
+    assert_eq!(1, checklists.len());
+    assert_eq!("Jozin's", checklists[0].title);
+
+    let foreign = client
+        .get(format!("{}/checklists/{}", &app.address, checklists[0].id))
+        .bearer_auth(&krtek)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(404, foreign.status().as_u16());
+}
+
+#[tokio::test]
+async fn creating_checklist_without_title_returns_422() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    let response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"title": " ", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn checklist_routes_require_authorization() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/checklists", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn getting_checklist_with_invalid_id_returns_400() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    let response = client
+        .get(format!("{}/checklists/not-a-uuid", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn updating_checklist_keeps_task_ids() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let created = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [{"title": "Check fuel"}, {"title": "Check tyres"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let (fuel, tyres) = (&created.tasks[0], &created.tasks[1]);
+    assert_ne!(fuel.id, tyres.id);
+
+    // Act
+    let response = client
+        .put(format!("{}/checklists/{}", &app.address, created.id))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [
+                {"id": tyres.id, "title": "Check tyre pressure"},
+                {"title": "Check oil"},
+                {"id": fuel.id, "title": "Check fuel"}
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let updated = response.json::<Checklist>().await.unwrap();
+    assert_eq!(tyres.id, updated.tasks[0].id);
+    assert_eq!("Check tyre pressure", updated.tasks[0].title);
+    assert!(![fuel.id, tyres.id].contains(&updated.tasks[1].id));
+    assert_eq!(fuel.id, updated.tasks[2].id);
+}
+
+#[tokio::test]
+async fn updating_checklist_with_duplicate_task_ids_returns_422() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let created = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"title": "Pre-flight", "tasks": [{"title": "Check fuel"}]}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let task_id = created.tasks[0].id;
+
+    let response = client
+        .put(format!("{}/checklists/{}", &app.address, created.id))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [{"id": task_id, "title": "a"}, {"id": task_id, "title": "b"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn editing_checklist_creates_versions_that_can_be_reverted() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let created = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"title": "Pre-flight", "tasks": [{"title": "Check fuel"}]}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let checklist_url = format!("{}/checklists/{}", &app.address, created.id);
+
+    // Act
+    let updated = client
+        .put(&checklist_url)
+        .bearer_auth(&token)
+        .json(&json!({"title": "Pre-flight v2", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let unchanged = client
+        .put(&checklist_url)
+        .bearer_auth(&token)
+        .json(&json!({"title": "Pre-flight v2", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let first = client
+        .get(format!("{}/versions/1", &checklist_url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let reverted = client
+        .post(format!("{}/versions/1/revert", &checklist_url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let versions = client
+        .get(format!("{}/versions", &checklist_url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<ChecklistVersionSummary>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(1, created.version);
+    assert_eq!(2, updated.version);
+    assert_eq!(2, unchanged.version);
+    assert_eq!(created, first);
+    assert_eq!(3, reverted.version);
+    assert_eq!(created.title, reverted.title);
+    assert_eq!(created.tasks, reverted.tasks);
+    assert_eq!(
+        vec![(1, "Pre-flight"), (2, "Pre-flight v2"), (3, "Pre-flight")],
+        versions
+            .iter()
+            .map(|v| (v.version, v.title.as_str()))
+            .collect::<Vec<_>>()
+    );
+    assert!(versions
+        .iter()
+        .all(|v| v.created_by.as_deref() == Some("jozin")));
+}
+
+#[tokio::test]
+async fn reverting_to_version_with_include_cycle_returns_422() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let briefing_id = app.create_checklist(&token).await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Departure",
+            "tasks": [{"title": "Briefing", "include": {"checklist_id": briefing_id}}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+    let checklist_url = format!("{}/checklists/{}", &app.address, checklist.id);
+    client
+        .put(&checklist_url)
+        .bearer_auth(&token)
+        .json(&json!({"title": "Departure", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    client
+        .put(format!("{}/checklists/{}", &app.address, briefing_id))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [{"title": "Departure", "include": {"checklist_id": checklist.id}}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Act
+    let response = client
+        .post(format!("{}/versions/1/revert", &checklist_url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn getting_missing_checklist_version_returns_404() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let created = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"title": "Pre-flight", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap();
+
+    let response = client
+        .get(format!(
+            "{}/checklists/{}/versions/2",
+            &app.address, created.id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_checklist_returns_422_for_invalid_task_input() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [{"title": "Outcome", "input": {"type": "choice", "options": []}}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_checklist_returns_422_for_condition_on_later_task() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let later_id = uuid::Uuid::new_v4();
+
+    // Act
+    let response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [
+                {"title": "Drain fuel sample", "visible_if": {"task_id": later_id, "equals": true}},
+                {"id": later_id, "title": "Water detected", "input": {"type": "boolean"}}
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_checklist_returns_422_for_undeclared_placeholder() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Deploy",
+            "variables": [{"name": "hostname"}],
+            "tasks": [{"title": "Restart {{service}} on {{hostname}}"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_checklist_returns_422_for_list_used_outside_repeated_section() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Rollout",
+            "variables": [{"name": "hostnames", "list": true}],
+            "tasks": [{"title": "Drain {{hostnames}}"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_checklist_returns_422_for_section_requiring_verification() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Sterilisation",
+            "tasks": [{
+                "title": "Autoclave",
+                "requires_verification": true,
+                "children": [{"title": "Load"}]
+            }]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
@@ -0,0 +1,284 @@
+mod common;
+
+use serde_json::json;
+use uuid::Uuid;
+use webapi::models::{comment::Comment, execution::Execution};
+
+async fn get_execution(app: &common::TestApp, token: &str, execution_id: Uuid) -> Execution {
+    reqwest::Client::new()
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Execution>()
+        .await
+        .unwrap()
+}
+
+async fn comment(
+    app: &common::TestApp,
+    token: &str,
+    execution: &Execution,
+    task: Option<Uuid>,
+    body: &str,
+) -> reqwest::Response {
+    let url = match task {
+        Some(task) => format!(
+            "{}/executions/{}/tasks/{}/comments",
+            &app.address, execution.id, task
+        ),
+        None => format!("{}/executions/{}/comments", &app.address, execution.id),
+    };
+    reqwest::Client::new()
+        .post(url)
+        .bearer_auth(token)
+        .json(&json!({ "body": body }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn comments_are_counted_and_listed_per_task() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let execution = get_execution(&app, &token, execution_id).await;
+    let task_id = execution.task_statuses[1].id;
+
+    // Act
+    comment(&app, &token, &execution, None, "Delayed by weather").await;
+    comment(&app, &token, &execution, Some(task_id), "Left tyre worn").await;
+    comment(&app, &token, &execution, Some(task_id), "Replaced").await;
+    let counted = get_execution(&app, &token, execution.id).await;
+    let thread = client
+        .get(format!(
+            "{}/executions/{}/tasks/{}/comments",
+            &app.address, execution.id, task_id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<Comment>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(3, counted.comments.total);
+    assert_eq!(Some(&2), counted.comments.tasks.get(&task_id));
+    assert_eq!(2, thread.len());
+    assert_eq!("Left tyre worn", thread[0].body);
+    assert_eq!("jozin", thread[0].author);
+    assert_eq!(Some(task_id), thread[1].task_id);
+}
+
+#[tokio::test]
+async fn author_edits_and_deletes_comment() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let execution = get_execution(&app, &token, execution_id).await;
+    let created = comment(&app, &token, &execution, None, "Delayed")
+        .await
+        .json::<Comment>()
+        .await
+        .unwrap();
+    let url = format!(
+        "{}/executions/{}/comments/{}",
+        &app.address, execution.id, created.id
+    );
+
+    // Act
+    let updated = client
+        .put(&url)
+        .bearer_auth(&token)
+        .json(&json!({"body": "Delayed by weather"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Comment>()
+        .await
+        .unwrap();
+    let delete_response = client
+        .delete(&url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let counted = get_execution(&app, &token, execution.id).await;
+
+    // Assert
+    assert_eq!("Delayed by weather", updated.body);
+    assert!(updated.updated_at.is_some());
+    assert_eq!(204, delete_response.status().as_u16());
+    assert_eq!(0, counted.comments.total);
+}
+
+#[tokio::test]
+async fn only_author_changes_comment() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let team = client
+        .post(format!("{}/teams", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"name": "Hangar"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    client
+        .put(format!(
+            "{}/teams/{}/members/krtek",
+            &app.address,
+            team["id"].as_str().unwrap()
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"role": "owner"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"team_id": team["id"], "title": "Pre-flight", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let execution = get_execution(&app, &token, execution_id).await;
+    let created = comment(&app, &token, &execution, None, "Delayed")
+        .await
+        .json::<Comment>()
+        .await
+        .unwrap();
+
+    // Act
+    let response = client
+        .delete(format!(
+            "{}/executions/{}/comments/{}",
+            &app.address, execution.id, created.id
+        ))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn author_changes_comment_after_losing_execute_role() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let team = client
+        .post(format!("{}/teams", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"name": "Hangar"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let member_url = format!(
+        "{}/teams/{}/members/krtek",
+        &app.address,
+        team["id"].as_str().unwrap()
+    );
+    client
+        .put(&member_url)
+        .bearer_auth(&token)
+        .json(&json!({"role": "executor"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"team_id": team["id"], "title": "Pre-flight", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let execution = get_execution(&app, &token, execution_id).await;
+    let created = comment(&app, &other_token, &execution, None, "Delayed")
+        .await
+        .json::<Comment>()
+        .await
+        .unwrap();
+    client
+        .put(&member_url)
+        .bearer_auth(&token)
+        .json(&json!({"role": "viewer"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Act
+    let create_response = comment(&app, &other_token, &execution, None, "Cancelled").await;
+    let update_response = client
+        .put(format!(
+            "{}/executions/{}/comments/{}",
+            &app.address, execution.id, created.id
+        ))
+        .bearer_auth(&other_token)
+        .json(&json!({"body": "Delayed by an hour"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let delete_response = client
+        .delete(format!(
+            "{}/executions/{}/comments/{}",
+            &app.address, execution.id, created.id
+        ))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, create_response.status().as_u16());
+    assert_eq!(200, update_response.status().as_u16());
+    assert_eq!(204, delete_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn comment_returns_422_for_empty_body() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let execution = get_execution(&app, &token, execution_id).await;
+
+    // Act
+    let response = comment(&app, &token, &execution, None, "  ").await;
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
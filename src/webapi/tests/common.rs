@@ -1,10 +1,15 @@
+#![allow(dead_code)]
+
 use once_cell::sync::Lazy;
 use secrecy::ExposeSecret;
 use sqlx::{Connection, Executor, PgConnection, PgPool};
-use std::{net::TcpListener, str::FromStr};
+use std::{net::TcpListener, path::PathBuf, str::FromStr, sync::Arc};
 use tracing::log::Level;
 use uuid::Uuid;
-use webapi::{configuration, startup, telemetry};
+use webapi::{
+    configuration, notifier::FileNotifier, routes::execution::ExecuteChecklistResponse, startup,
+    telemetry,
+};
 
 // Ensure that the `tracing` stack is only initialized once using `once_cell`
 static TRACING: Lazy<()> = Lazy::new(|| {
@@ -22,6 +27,77 @@ static TRACING: Lazy<()> = Lazy::new(|| {
 pub struct TestApp {
     pub address: String,
     pub db_pool: PgPool,
+    pub notifications_path: PathBuf,
+}
+
+impl TestApp {
+    /// Creates a new user and logs them in, returning their session token.
+    pub async fn create_logged_in_user(&self, username: &str) -> String {
+        let client = reqwest::Client::new();
+        let credentials = serde_json::json!({"username": username, "password": "123"});
+
+        client
+            .post(format!("{}/user", &self.address))
+            .json(&credentials)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        let response = client
+            .post(format!("{}/user/login", &self.address))
+            .json(&credentials)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        response.json::<serde_json::Value>().await.unwrap()["token"]
+            .as_str()
+            .expect("a valid login response")
+            .to_owned()
+    }
+
+    /// Creates a checklist with two tasks, returning its id.
+    pub async fn create_checklist(&self, token: &str) -> Uuid {
+        let checklist = reqwest::Client::new()
+            .post(format!("{}/checklists", &self.address))
+            .bearer_auth(token)
+            .json(&serde_json::json!({
+                "title": "Pre-flight",
+                "tasks": [{"title": "Check fuel"}, {"title": "Check tyres"}]
+            }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .json::<serde_json::Value>()
+            .await
+            .unwrap();
+        Uuid::parse_str(checklist["id"].as_str().expect("a created checklist")).unwrap()
+    }
+
+    /// Starts an execution of the checklist, returning its id.
+    pub async fn start_execution(&self, token: &str, checklist_id: Uuid) -> Uuid {
+        reqwest::Client::new()
+            .post(format!(
+                "{}/checklists/{}/execute",
+                &self.address, checklist_id
+            ))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .json::<ExecuteChecklistResponse>()
+            .await
+            .unwrap()
+            .execution_id
+    }
+
+    /// Returns notifications sent to users so far, oldest first.
+    pub fn notifications(&self) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(&self.notifications_path)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("a valid notification"))
+            .collect()
+    }
 }
 
 pub async fn spawn_app() -> TestApp {
@@ -38,12 +114,25 @@ pub async fn spawn_app() -> TestApp {
     configuration.database.database_name = Uuid::new_v4().to_string();
     let connection_pool = configure_database(&configuration.database).await;
 
-    let server = startup::run(listener, connection_pool.clone()).expect("Failed to bind address");
+    let notifications_path = std::env::temp_dir().join(format!(
+        "{}.notifications",
+        configuration.database.database_name
+    ));
+    let notifier = Arc::new(FileNotifier::new(&notifications_path));
+
+    let server = startup::run(
+        listener,
+        connection_pool.clone(),
+        configuration.session,
+        notifier,
+    )
+    .expect("Failed to bind address");
     #[allow(clippy::let_underscore_future)]
     let _ = tokio::spawn(server);
     TestApp {
         address,
         db_pool: connection_pool,
+        notifications_path,
     }
 }
 
@@ -0,0 +1,1166 @@
+mod common;
+
+use serde_json::json;
+use uuid::Uuid;
+use webapi::{
+    models::{
+        checklist::VariableValue,
+        execution::{Execution, ExecutionSummary, SignatureMeaning, State, TaskStatus},
+    },
+    routes::execution::ExecuteChecklistResponse,
+};
+
+#[tokio::test]
+async fn executing_checklist_snapshots_its_tasks() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+
+    // Act
+    let response = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address, checklist_id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(200, response.status().as_u16());
+    let execution_id = response
+        .json::<ExecuteChecklistResponse>()
+        .await
+        .unwrap()
+        .execution_id;
+
+    client
+        .put(format!("{}/checklists/{}", &app.address, checklist_id))
+        .bearer_auth(&token)
+        .json(&json!({"title": "Changed", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    let execution = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, execution.status().as_u16());
+    let execution = execution.json::<Execution>().await.unwrap();
+    assert_eq!(execution.checklist_id, checklist_id);
+    assert_eq!(execution.checklist_version, 1);
+    assert_eq!(execution.title, "Pre-flight");
+    assert_eq!(execution.state, State::NotStarted);
+    assert_eq!(
+        vec!["Check fuel", "Check tyres"],
+        execution
+            .task_statuses
+            .iter()
+            .map(|t| t.title.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert!(execution
+        .task_statuses
+        .iter()
+        .all(|t| t.state == State::NotStarted));
+}
+
+#[tokio::test]
+async fn listing_executions_returns_only_own_executions() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let jozin = app.create_logged_in_user("jozin").await;
+    let krtek = app.create_logged_in_user("krtek").await;
+    let checklist_id = app.create_checklist(&jozin).await;
+    let execution_id = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address, checklist_id
+        ))
+        .bearer_auth(&jozin)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<ExecuteChecklistResponse>()
+        .await
+        .unwrap()
+        .execution_id;
+
+    // Act
+    let own = client
+        .get(format!("{}/executions", &app.address))
+        .bearer_auth(&jozin)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<ExecutionSummary>>()
+        .await
+        .unwrap();
+    let foreign = client
+        .get(format!("{}/executions", &app.address))
+        .bearer_auth(&krtek)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<ExecutionSummary>>()
+        .await
+        .unwrap();
+    let foreign_get = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&krtek)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(1, own.len());
+    assert_eq!(execution_id, own[0].id);
+    assert!(foreign.is_empty());
+    assert_eq!(404, foreign_get.status().as_u16());
+}
+
+#[tokio::test]
+async fn executing_someone_elses_checklist_returns_404() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let jozin = app.create_logged_in_user("jozin").await;
+    let krtek = app.create_logged_in_user("krtek").await;
+    let checklist_id = app.create_checklist(&jozin).await;
+
+    let response = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address, checklist_id
+        ))
+        .bearer_auth(&krtek)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn execution_cannot_be_done_until_required_tasks_are_complete() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+
+    // Act
+    let task_response = client
+        .put(format!(
+            "{}/executions/{}/tasks/0/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let premature_done = client
+        .put(format!(
+            "{}/executions/{}/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    client
+        .put(format!(
+            "{}/executions/{}/tasks/1/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "skipped", "reason": "new tyres"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let done = client
+        .put(format!(
+            "{}/executions/{}/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, task_response.status().as_u16());
+    assert_eq!(
+        State::Done,
+        task_response
+            .json::<TaskStatus>()
+            .await
+            .expect("a valid task status")
+            .state
+    );
+
+    assert_eq!(409, premature_done.status().as_u16());
+    let error = premature_done.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("incomplete_tasks", error["code"]);
+    assert_eq!("required tasks are not complete", error["error"]);
+    assert_eq!(1, error["tasks"].as_array().unwrap().len());
+    assert_eq!("Check tyres", error["tasks"][0]["title"]);
+
+    assert_eq!(200, done.status().as_u16());
+    let execution = done.json::<Execution>().await.unwrap();
+    assert_eq!(State::Done, execution.state);
+    assert_eq!(
+        Some("new tyres"),
+        execution.task_statuses[1].reason.as_deref()
+    );
+}
+
+#[tokio::test]
+async fn invalid_task_transitions_are_rejected() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let task_url = format!("{}/executions/{}/tasks/0/state", &app.address, execution_id);
+
+    // Act
+    let skip_without_reason = client
+        .put(&task_url)
+        .bearer_auth(&token)
+        .json(&json!({"state": "skipped"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    client
+        .put(&task_url)
+        .bearer_auth(&token)
+        .json(&json!({"state": "blocked"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let done_while_blocked = client
+        .put(&task_url)
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let missing_task = client
+        .put(format!(
+            "{}/executions/{}/tasks/5/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, skip_without_reason.status().as_u16());
+    assert_eq!(409, done_while_blocked.status().as_u16());
+    assert_eq!(
+        json!({
+            "code": "invalid_transition",
+            "error": "cannot change state from 'blocked' to 'done'",
+            "from": "blocked",
+            "to": "done"
+        }),
+        done_while_blocked
+            .json::<serde_json::Value>()
+            .await
+            .unwrap()
+    );
+    assert_eq!(404, missing_task.status().as_u16());
+}
+
+#[tokio::test]
+async fn execution_tasks_can_be_addressed_by_id() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let execution = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Execution>()
+        .await
+        .unwrap();
+    let tyres_id = execution.task_statuses[1].id;
+
+    // Act
+    let response = client
+        .put(format!(
+            "{}/executions/{}/tasks/{}/state",
+            &app.address, execution_id, tyres_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let unknown_task = client
+        .put(format!(
+            "{}/executions/{}/tasks/{}/state",
+            &app.address,
+            execution_id,
+            Uuid::new_v4()
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let task_status = response.json::<TaskStatus>().await.unwrap();
+    assert_eq!(tyres_id, task_status.id);
+    assert_eq!("Check tyres", task_status.title);
+    assert_eq!(State::Done, task_status.state);
+    assert_eq!(404, unknown_task.status().as_u16());
+}
+
+#[tokio::test]
+async fn nested_tasks_derive_parent_state_and_progress() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [{
+                "title": "Cabin",
+                "children": [
+                    {"title": "Doors", "children": [{"title": "Left"}, {"title": "Right"}]},
+                    {"title": "Seats"}
+                ]
+            }]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let left_id = checklist["tasks"][0]["children"][0]["children"][0]["id"]
+        .as_str()
+        .unwrap();
+
+    // Act
+    let left = client
+        .put(format!(
+            "{}/executions/{}/tasks/{}/state",
+            &app.address, execution_id, left_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let section = client
+        .put(format!(
+            "{}/executions/{}/tasks/0/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let execution = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Execution>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, left.status().as_u16());
+    assert_eq!(409, section.status().as_u16());
+    let cabin = &execution.task_statuses[0];
+    assert_eq!(State::InProgress, cabin.state);
+    assert_eq!(State::InProgress, cabin.children[0].state);
+    assert_eq!(State::Done, cabin.children[0].children[0].state);
+    assert_eq!(State::NotStarted, cabin.children[1].state);
+    assert_eq!(1, execution.progress.completed);
+    assert_eq!(3, execution.progress.total);
+}
+
+#[tokio::test]
+async fn task_values_are_validated_and_stored() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [
+                {"title": "Tyre pressure", "input": {"type": "number", "unit": "psi", "max": 50}},
+                {"title": "Outcome", "input": {"type": "choice", "options": ["pass", "fail"]}}
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let update_task = |task: usize, body: serde_json::Value| {
+        client
+            .put(format!(
+                "{}/executions/{}/tasks/{}/state",
+                &app.address, execution_id, task
+            ))
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+    };
+
+    // Act
+    let missing = update_task(0, json!({"state": "done"})).await.unwrap();
+    let out_of_range = update_task(0, json!({"state": "done", "value": 80}))
+        .await
+        .unwrap();
+    let not_an_option = update_task(1, json!({"state": "done", "value": "maybe"}))
+        .await
+        .unwrap();
+    let pressure = update_task(0, json!({"state": "done", "value": 32.5}))
+        .await
+        .unwrap();
+    let execution = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Execution>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(422, missing.status().as_u16());
+    let error = missing.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("value_required", error["code"]);
+    assert_eq!(422, out_of_range.status().as_u16());
+    assert_eq!(422, not_an_option.status().as_u16());
+    assert_eq!(200, pressure.status().as_u16());
+    assert_eq!(State::Done, execution.task_statuses[0].state);
+    assert_eq!(Some(json!(32.5)), execution.task_statuses[0].value);
+    assert_eq!(None, execution.task_statuses[1].value);
+}
+
+#[tokio::test]
+async fn conditional_tasks_are_shown_based_on_answers() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let water_id = Uuid::new_v4();
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Pre-flight",
+            "tasks": [
+                {"id": water_id, "title": "Water detected", "input": {"type": "boolean"}},
+                {"title": "Drain fuel sample", "visible_if": {"task_id": water_id, "equals": true}}
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let get_execution = || async {
+        client
+            .get(format!("{}/executions/{}", &app.address, execution_id))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .json::<Execution>()
+            .await
+            .unwrap()
+    };
+
+    // Act
+    let before = get_execution().await;
+    let hidden_update = client
+        .put(format!(
+            "{}/executions/{}/tasks/1/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    client
+        .put(format!(
+            "{}/executions/{}/tasks/{}/state",
+            &app.address, execution_id, water_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done", "value": true}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let after = get_execution().await;
+
+    // Assert
+    assert!(!before.task_statuses[1].visible);
+    assert_eq!(1, before.progress.total);
+    assert_eq!(409, hidden_update.status().as_u16());
+    assert!(after.task_statuses[1].visible);
+    assert_eq!(2, after.progress.total);
+}
+
+#[tokio::test]
+async fn out_of_order_completion_names_blocking_tasks() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Change",
+            "tasks": [{
+                "title": "Runbook",
+                "sequential": true,
+                "children": [{"title": "Drain traffic"}, {"title": "Restart service"}]
+            }]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let restart_id = checklist["tasks"][0]["children"][1]["id"].as_str().unwrap();
+
+    // Act
+    let response = client
+        .put(format!(
+            "{}/executions/{}/tasks/{}/state",
+            &app.address, execution_id, restart_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+    let error = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("blocked_by_tasks", error["code"]);
+    assert_eq!("Drain traffic", error["tasks"][0]["title"]);
+}
+
+#[tokio::test]
+async fn execution_renders_checklist_variables() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Deploy {{release_version}}",
+            "variables": [{"name": "hostname"}, {"name": "release_version"}],
+            "tasks": [{"title": "Restart {{hostname}}"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = checklist["id"].as_str().unwrap();
+    let execute = |body: serde_json::Value| {
+        client
+            .post(format!(
+                "{}/checklists/{}/execute",
+                &app.address, checklist_id
+            ))
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+    };
+
+    // Act
+    let missing = execute(json!({"variables": {"hostname": "db-1"}}))
+        .await
+        .unwrap();
+    let execution_id = execute(json!({
+        "variables": {"hostname": "db-1", "release_version": "1.4.2"}
+    }))
+    .await
+    .unwrap()
+    .json::<ExecuteChecklistResponse>()
+    .await
+    .unwrap()
+    .execution_id;
+    let execution = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Execution>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(422, missing.status().as_u16());
+    assert_eq!("Deploy 1.4.2", execution.title);
+    assert_eq!("Restart db-1", execution.task_statuses[0].title);
+    assert_eq!(
+        VariableValue::Text("db-1".to_owned()),
+        execution.variables["hostname"]
+    );
+}
+
+#[tokio::test]
+async fn included_checklist_is_resolved_when_execution_starts() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let briefing_id = app.create_checklist(&token).await;
+    let procedure = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Procedure",
+            "tasks": [{"title": "Briefing", "include": {"checklist_id": briefing_id}}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let procedure_id = Uuid::parse_str(procedure["id"].as_str().unwrap()).unwrap();
+    client
+        .put(format!("{}/checklists/{}", &app.address, briefing_id))
+        .bearer_auth(&token)
+        .json(&json!({"title": "Briefing", "tasks": [{"title": "Check exits"}]}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Act
+    let cycle = client
+        .put(format!("{}/checklists/{}", &app.address, briefing_id))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Briefing",
+            "tasks": [{"title": "Procedure", "include": {"checklist_id": procedure_id}}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let execution_id = app.start_execution(&token, procedure_id).await;
+    let execution = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Execution>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(422, cycle.status().as_u16());
+    let briefing = &execution.task_statuses[0];
+    assert_eq!(Some(2), briefing.include.as_ref().unwrap().version);
+    assert_eq!("Check exits", briefing.children[0].title);
+    assert_eq!(1, execution.progress.total);
+}
+
+#[tokio::test]
+async fn included_checklist_does_not_need_to_be_shared_with_executor() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let briefing_id = app.create_checklist(&token).await;
+    let procedure = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Procedure",
+            "tasks": [{"title": "Briefing", "include": {"checklist_id": briefing_id}}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let procedure_id = procedure["id"].as_str().unwrap();
+    client
+        .put(format!(
+            "{}/checklists/{}/grants/krtek",
+            &app.address, procedure_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"access": "execute"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Act
+    let response = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address, procedure_id
+        ))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let including_response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&other_token)
+        .json(&json!({
+            "title": "Copy",
+            "tasks": [{"title": "Briefing", "include": {"checklist_id": briefing_id}}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(422, including_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn repeated_section_has_an_instance_per_item() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Rollout",
+            "variables": [{"name": "hostnames", "list": true}],
+            "tasks": [{
+                "title": "Servers",
+                "repeat_for": "hostnames",
+                "children": [{"title": "Drain {{hostnames}}"}, {"title": "Upgrade"}]
+            }]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let execution_id = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address,
+            checklist["id"].as_str().unwrap()
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"variables": {"hostnames": ["web-1", "web-2"]}}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<ExecuteChecklistResponse>()
+        .await
+        .unwrap()
+        .execution_id;
+    let get_execution = || async {
+        client
+            .get(format!("{}/executions/{}", &app.address, execution_id))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .json::<Execution>()
+            .await
+            .unwrap()
+    };
+    let drain_web_2 = get_execution().await.task_statuses[0].children[1].children[0].id;
+
+    // Act
+    client
+        .put(format!(
+            "{}/executions/{}/tasks/{}/state",
+            &app.address, execution_id, drain_web_2
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let execution = get_execution().await;
+
+    // Assert
+    let instances = &execution.task_statuses[0].children;
+    assert_eq!(2, instances.len());
+    assert_eq!(Some("web-1".to_owned()), instances[0].item);
+    assert_eq!("Drain web-1", instances[0].children[0].title);
+    assert_eq!(State::NotStarted, instances[0].state);
+    assert_eq!(State::InProgress, instances[1].state);
+    assert_eq!(Some(1), instances[1].progress.map(|p| p.completed));
+    assert_eq!(4, execution.progress.total);
+}
+
+/// Creates a checklist in a team of `jozin` where `krtek` and `mach` are executors, and
+/// starts its execution as `jozin`.
+async fn start_team_execution(app: &common::TestApp, token: &str) -> Uuid {
+    let client = reqwest::Client::new();
+    let team = client
+        .post(format!("{}/teams", &app.address))
+        .bearer_auth(token)
+        .json(&json!({"name": "Hangar"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    for username in ["krtek", "mach"] {
+        client
+            .put(format!(
+                "{}/teams/{}/members/{}",
+                &app.address,
+                team["id"].as_str().unwrap(),
+                username
+            ))
+            .bearer_auth(token)
+            .json(&json!({"role": "executor"}))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+    }
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(token)
+        .json(&json!({
+            "team_id": team["id"],
+            "title": "Pre-flight",
+            "tasks": [{"title": "Check fuel"}, {"title": "Check tyres"}, {"title": "Check oil"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    app.start_execution(token, checklist_id).await
+}
+
+#[tokio::test]
+async fn assigned_tasks_are_listed_by_due_date_and_changed_only_by_assignee() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let assignee_token = app.create_logged_in_user("krtek").await;
+    let other_token = app.create_logged_in_user("mach").await;
+    let team_owner_token = app.create_logged_in_user("brum").await;
+    let execution_id = start_team_execution(&app, &token).await;
+    let team_id = sqlx::query!("SELECT id FROM teams")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap()
+        .id;
+    client
+        .put(format!("{}/teams/{}/members/brum", &app.address, team_id))
+        .bearer_auth(&token)
+        .json(&json!({"role": "owner"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    for (task, due_at) in [
+        (0, json!("2026-10-20T08:00:00Z")),
+        (1, json!("2026-10-19T08:00:00Z")),
+        (2, json!(null)),
+    ] {
+        let response = client
+            .put(format!(
+                "{}/executions/{}/tasks/{}/assignee",
+                &app.address, execution_id, task
+            ))
+            .bearer_auth(&token)
+            .json(&json!({"assignee": "krtek", "due_at": due_at}))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    // Act
+    let tasks = client
+        .get(format!("{}/user/tasks", &app.address))
+        .bearer_auth(&assignee_token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+    let mut responses = Vec::new();
+    for (task, token) in [
+        (0, &other_token),
+        (0, &team_owner_token),
+        (1, &assignee_token),
+        (2, &token),
+    ] {
+        let response = client
+            .put(format!(
+                "{}/executions/{}/tasks/{}/state",
+                &app.address, execution_id, task
+            ))
+            .bearer_auth(token)
+            .json(&json!({"state": "done"}))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        responses.push(response.status().as_u16());
+    }
+    let remaining_tasks = client
+        .get(format!("{}/user/tasks", &app.address))
+        .bearer_auth(&assignee_token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<serde_json::Value>>()
+        .await
+        .unwrap();
+
+    // Assert
+    let titles: Vec<_> = tasks.iter().map(|t| t["title"].as_str().unwrap()).collect();
+    assert_eq!(vec!["Check tyres", "Check fuel", "Check oil"], titles);
+    assert_eq!(json!(execution_id), tasks[0]["execution_id"]);
+    assert_eq!(vec![403, 403, 200, 200], responses);
+    assert_eq!(1, remaining_tasks.len());
+    assert_eq!("Check fuel", remaining_tasks[0]["title"]);
+}
+
+#[tokio::test]
+async fn tasks_cannot_be_assigned_to_users_without_access() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let token = app.create_logged_in_user("jozin").await;
+    app.create_logged_in_user("krtek").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .put(format!(
+            "{}/executions/{}/tasks/0/assignee",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"assignee": "krtek"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn tasks_requiring_verification_need_a_second_user() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let verifier_token = app.create_logged_in_user("krtek").await;
+    let team = client
+        .post(format!("{}/teams", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"name": "Lab"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    client
+        .put(format!(
+            "{}/teams/{}/members/krtek",
+            &app.address,
+            team["id"].as_str().unwrap()
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"role": "executor"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "team_id": team["id"],
+            "title": "Sample intake",
+            "tasks": [{"title": "Weigh sample", "requires_verification": true}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    client
+        .put(format!(
+            "{}/executions/{}/tasks/0/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let verify = |token: String| {
+        client
+            .post(format!(
+                "{}/executions/{}/tasks/0/verify",
+                &app.address, execution_id
+            ))
+            .bearer_auth(token)
+            .send()
+    };
+    let finish = |token: String| {
+        client
+            .put(format!(
+                "{}/executions/{}/state",
+                &app.address, execution_id
+            ))
+            .bearer_auth(token)
+            .json(&json!({"state": "done"}))
+            .send()
+    };
+
+    // Act
+    let unverified_response = finish(token.clone()).await.unwrap();
+    let self_response = verify(token.clone()).await.unwrap();
+    let verified = verify(verifier_token.clone())
+        .await
+        .unwrap()
+        .json::<TaskStatus>()
+        .await
+        .unwrap();
+    let finish_response = finish(token.clone()).await.unwrap();
+
+    // Assert
+    assert_eq!(409, unverified_response.status().as_u16());
+    assert_eq!(403, self_response.status().as_u16());
+    assert_eq!(Some("jozin".to_owned()), verified.performed_by);
+    assert_eq!(Some("krtek".to_owned()), verified.verified_by);
+    assert_eq!(200, finish_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn tasks_requiring_signature_need_the_users_password() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({
+            "title": "Batch release",
+            "tasks": [{"title": "Approve release", "requires_signature": true}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let checklist_id = Uuid::parse_str(checklist["id"].as_str().unwrap()).unwrap();
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let update = |body: serde_json::Value| {
+        client
+            .put(format!(
+                "{}/executions/{}/tasks/0/state",
+                &app.address, execution_id
+            ))
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+    };
+
+    // Act
+    let unsigned_response = update(json!({"state": "done"})).await.unwrap();
+    let wrong_password_response = update(json!({
+        "state": "done",
+        "signature": {"password": "wrong", "meaning": "approved"}
+    }))
+    .await
+    .unwrap();
+    let signed = update(json!({
+        "state": "done",
+        "signature": {"password": "123", "meaning": "approved"}
+    }))
+    .await
+    .unwrap()
+    .json::<TaskStatus>()
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(422, unsigned_response.status().as_u16());
+    assert_eq!(403, wrong_password_response.status().as_u16());
+    assert_eq!(State::Done, signed.state);
+    let signature = signed.signature.unwrap();
+    assert_eq!("jozin", signature.username);
+    assert_eq!(SignatureMeaning::Approved, signature.meaning);
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use serde_json::json;
+use uuid::Uuid;
+use webapi::models::{
+    checklist::ChecklistSummary,
+    grant::{Access, Grant},
+};
+
+async fn grant(
+    app: &common::TestApp,
+    token: &str,
+    checklist_id: Uuid,
+    username: &str,
+    access: &str,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .put(format!(
+            "{}/checklists/{}/grants/{}",
+            &app.address, checklist_id, username
+        ))
+        .bearer_auth(token)
+        .json(&json!({ "access": access }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn shared_checklists_are_listed_as_shared() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let checklist_id = app.create_checklist(&token).await;
+    app.create_checklist(&other_token).await;
+
+    // Act
+    let response = grant(&app, &token, checklist_id, "krtek", "read").await;
+    let checklists = client
+        .get(format!("{}/checklists", &app.address))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<ChecklistSummary>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(2, checklists.len());
+    assert_eq!(checklist_id, checklists[0].id);
+    assert!(checklists[0].shared);
+    assert!(!checklists[1].shared);
+}
+
+#[tokio::test]
+async fn execute_grant_allows_executing_but_not_editing() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let checklist_id = app.create_checklist(&token).await;
+    grant(&app, &token, checklist_id, "krtek", "execute").await;
+
+    // Act
+    let execute_response = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address, checklist_id
+        ))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let update_response = client
+        .put(format!("{}/checklists/{}", &app.address, checklist_id))
+        .bearer_auth(&other_token)
+        .json(&json!({"title": "Store closing", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, execute_response.status().as_u16());
+    assert_eq!(403, update_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn only_owner_manages_grants() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    app.create_logged_in_user("mach").await;
+    let checklist_id = app.create_checklist(&token).await;
+    grant(&app, &token, checklist_id, "krtek", "edit").await;
+
+    // Act
+    let editor_response = grant(&app, &other_token, checklist_id, "mach", "read").await;
+    let grants = client
+        .get(format!(
+            "{}/checklists/{}/grants",
+            &app.address, checklist_id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<Grant>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(403, editor_response.status().as_u16());
+    assert_eq!(1, grants.len());
+    assert_eq!("krtek", grants[0].username);
+    assert_eq!(Access::Edit, grants[0].access);
+}
+
+#[tokio::test]
+async fn revoked_grant_hides_checklist() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let checklist_id = app.create_checklist(&token).await;
+    grant(&app, &token, checklist_id, "krtek", "read").await;
+
+    // Act
+    let revoke_response = client
+        .delete(format!(
+            "{}/checklists/{}/grants/krtek",
+            &app.address, checklist_id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let get_response = client
+        .get(format!("{}/checklists/{}", &app.address, checklist_id))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(204, revoke_response.status().as_u16());
+    assert_eq!(404, get_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn grant_returns_404_for_unknown_user() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+
+    // Act
+    let response = grant(&app, &token, checklist_id, "nobody", "read").await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
@@ -0,0 +1,356 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use serde_json::json;
+use webapi::models::mfa::{MfaStatus, TotpSecret};
+
+/// Enrols an authenticator for the user, returning its secret and the recovery codes.
+async fn enable_totp(app: &common::TestApp, token: &str) -> (TotpSecret, Vec<String>) {
+    let client = reqwest::Client::new();
+    client
+        .post(format!("{}/user/mfa/totp", &app.address))
+        .bearer_auth(token)
+        .json(&json!({"password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let secret = TotpSecret::from_bytes(
+        sqlx::query!("SELECT secret FROM totp_authenticators")
+            .fetch_one(&app.db_pool)
+            .await
+            .unwrap()
+            .secret,
+    );
+    let recovery_codes = client
+        .post(format!("{}/user/mfa/totp/confirm", &app.address))
+        .bearer_auth(token)
+        .json(&json!({"code": secret.code_at_time(Utc::now())}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap()["recovery_codes"]
+        .as_array()
+        .expect("a list of recovery codes")
+        .iter()
+        .map(|c| c.as_str().unwrap().to_owned())
+        .collect();
+    (secret, recovery_codes)
+}
+
+async fn log_in(app: &common::TestApp, username: &str) -> serde_json::Value {
+    reqwest::Client::new()
+        .post(format!("{}/user/login", &app.address))
+        .json(&json!({"username": username, "password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap()
+}
+
+async fn complete_login(app: &common::TestApp, request: serde_json::Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/user/login/mfa", &app.address))
+        .json(&request)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn enrolling_totp_returns_provisioning_uri() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let enrolment = client
+        .post(format!("{}/user/mfa/totp", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let status = client
+        .get(format!("{}/user/mfa", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<MfaStatus>()
+        .await
+        .unwrap();
+
+    // Assert
+    let secret = enrolment["secret"].as_str().unwrap();
+    assert_eq!(32, secret.len());
+    assert_eq!(
+        format!("otpauth://totp/Checkmate:jozin?secret={secret}&issuer=Checkmate&algorithm=SHA1&digits=6&period=30"),
+        enrolment["otpauth_uri"]
+    );
+    assert!(!status.totp_enabled);
+}
+
+#[tokio::test]
+async fn enrolling_totp_with_wrong_password_returns_403() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let response = client
+        .post(format!("{}/user/mfa/totp", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"password": "wrong"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn confirming_totp_with_wrong_code_returns_422() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    client
+        .post(format!("{}/user/mfa/totp", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Act
+    let response = client
+        .post(format!("{}/user/mfa/totp/confirm", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"code": "abcdef"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn login_of_enrolled_user_requires_code() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let (secret, recovery_codes) = enable_totp(&app, &token).await;
+
+    // Act
+    let login = log_in(&app, "jozin").await;
+    let challenge = login["challenge"].as_str().unwrap();
+    let wrong_response =
+        complete_login(&app, json!({"challenge": challenge, "code": "000000"})).await;
+    // The code used for confirming cannot be used again, so use the next one
+    let code = secret.code_at_time(Utc::now() + Duration::seconds(30));
+    let response = complete_login(&app, json!({"challenge": challenge, "code": code})).await;
+    let session_token = response.json::<serde_json::Value>().await.unwrap()["token"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    let reused_response = complete_login(&app, json!({"challenge": challenge, "code": code})).await;
+    let user_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&session_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(10, recovery_codes.len());
+    assert_eq!(true, login["mfa_required"]);
+    assert!(login.get("token").is_none());
+    assert_eq!(401, wrong_response.status().as_u16());
+    assert_eq!(401, reused_response.status().as_u16());
+    assert_eq!(200, user_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn recovery_code_can_be_used_once() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let (_, recovery_codes) = enable_totp(&app, &token).await;
+
+    // Act
+    let first_login = log_in(&app, "jozin").await;
+    let response = complete_login(
+        &app,
+        json!({"challenge": first_login["challenge"], "recovery_code": recovery_codes[0]}),
+    )
+    .await;
+    let second_login = log_in(&app, "jozin").await;
+    let reused_response = complete_login(
+        &app,
+        json!({"challenge": second_login["challenge"], "recovery_code": recovery_codes[0]}),
+    )
+    .await;
+    let status = client
+        .get(format!("{}/user/mfa", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<MfaStatus>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(401, reused_response.status().as_u16());
+    assert_eq!(
+        MfaStatus {
+            totp_enabled: true,
+            recovery_codes_left: 9
+        },
+        status
+    );
+}
+
+#[tokio::test]
+async fn regenerating_recovery_codes_requires_password() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let (_, recovery_codes) = enable_totp(&app, &token).await;
+
+    // Act
+    let wrong_password_response = client
+        .post(format!("{}/user/mfa/recovery-codes", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"password": "wrong"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let new_codes = client
+        .post(format!("{}/user/mfa/recovery-codes", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(403, wrong_password_response.status().as_u16());
+    assert_eq!(10, new_codes["recovery_codes"].as_array().unwrap().len());
+    assert_ne!(recovery_codes[0], new_codes["recovery_codes"][0]);
+}
+
+#[tokio::test]
+async fn challenge_is_revoked_after_too_many_wrong_codes() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let token = app.create_logged_in_user("jozin").await;
+    let (_, recovery_codes) = enable_totp(&app, &token).await;
+    let login = log_in(&app, "jozin").await;
+    for _ in 0..5 {
+        complete_login(
+            &app,
+            json!({"challenge": login["challenge"], "recovery_code": "wrong"}),
+        )
+        .await;
+    }
+
+    // Act
+    let response = complete_login(
+        &app,
+        json!({"challenge": login["challenge"], "recovery_code": recovery_codes[0]}),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn second_factor_is_locked_after_too_many_wrong_codes_across_logins() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let token = app.create_logged_in_user("jozin").await;
+    let (_, recovery_codes) = enable_totp(&app, &token).await;
+    for _ in 0..2 {
+        let login = log_in(&app, "jozin").await;
+        for _ in 0..5 {
+            complete_login(
+                &app,
+                json!({"challenge": login["challenge"], "recovery_code": "wrong"}),
+            )
+            .await;
+        }
+    }
+
+    // Act
+    let login_response = reqwest::Client::new()
+        .post(format!("{}/user/login", &app.address))
+        .json(&json!({"username": "jozin", "password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    sqlx::query!("UPDATE totp_authenticators SET locked_until = now() - interval '1 minute'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let login = log_in(&app, "jozin").await;
+    let response = complete_login(
+        &app,
+        json!({"challenge": login["challenge"], "recovery_code": recovery_codes[0]}),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(429, login_response.status().as_u16());
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn disabled_totp_is_no_longer_required_at_login() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    enable_totp(&app, &token).await;
+
+    // Act
+    let wrong_password_response = client
+        .delete(format!("{}/user/mfa/totp", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"password": "wrong"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let response = client
+        .delete(format!("{}/user/mfa/totp", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let login = log_in(&app, "jozin").await;
+
+    // Assert
+    assert_eq!(403, wrong_password_response.status().as_u16());
+    assert_eq!(204, response.status().as_u16());
+    assert!(login.get("token").is_some());
+}
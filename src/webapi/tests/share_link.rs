@@ -0,0 +1,136 @@
+mod common;
+
+use serde_json::json;
+use uuid::Uuid;
+use webapi::models::{
+    execution::State,
+    share_link::{CreatedShareLink, ShareLink, SharedExecution},
+};
+
+async fn create_share_link(
+    app: &common::TestApp,
+    token: &str,
+    execution_id: Uuid,
+    body: serde_json::Value,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!(
+            "{}/executions/{}/share-links",
+            &app.address, execution_id
+        ))
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn shared_execution_can_be_viewed_without_logging_in() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    client
+        .put(format!(
+            "{}/executions/{}/tasks/0/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let link = create_share_link(&app, &token, execution_id, json!({}))
+        .await
+        .json::<CreatedShareLink>()
+        .await
+        .unwrap();
+
+    // Act
+    let shared = client
+        .get(format!("{}/shared/{}", &app.address, link.token))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<SharedExecution>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!("Pre-flight", shared.title);
+    assert_eq!(State::InProgress, shared.state);
+    assert_eq!(1, shared.progress.completed);
+    assert_eq!(State::Done, shared.tasks[0].state);
+}
+
+#[tokio::test]
+async fn revoked_share_link_is_not_found() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+    let link = create_share_link(&app, &token, execution_id, json!({}))
+        .await
+        .json::<CreatedShareLink>()
+        .await
+        .unwrap();
+
+    // Act
+    let revoke_response = client
+        .delete(format!(
+            "{}/executions/{}/share-links/{}",
+            &app.address, execution_id, link.link.id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let links = client
+        .get(format!(
+            "{}/executions/{}/share-links",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<ShareLink>>()
+        .await
+        .unwrap();
+    let view_response = client
+        .get(format!("{}/shared/{}", &app.address, link.token))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(204, revoke_response.status().as_u16());
+    assert!(links.is_empty());
+    assert_eq!(404, view_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn share_link_returns_422_for_expiry_in_the_past() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let token = app.create_logged_in_user("jozin").await;
+    let checklist_id = app.create_checklist(&token).await;
+    let execution_id = app.start_execution(&token, checklist_id).await;
+
+    // Act
+    let response = create_share_link(
+        &app,
+        &token,
+        execution_id,
+        json!({"expires_at": "2020-01-01T00:00:00Z"}),
+    )
+    .await;
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+}
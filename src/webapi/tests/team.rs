@@ -0,0 +1,273 @@
+mod common;
+
+use serde_json::json;
+use uuid::Uuid;
+use webapi::models::{
+    checklist::{Checklist, ChecklistSummary},
+    team::{Role, Team},
+};
+
+/// Creates a team owned by the user with `token` and adds `members` to it.
+async fn create_team(app: &common::TestApp, token: &str, members: &[(&str, &str)]) -> Uuid {
+    let client = reqwest::Client::new();
+    let team = client
+        .post(format!("{}/teams", &app.address))
+        .bearer_auth(token)
+        .json(&json!({"name": "Operations"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Team>()
+        .await
+        .unwrap();
+    for (username, role) in members {
+        client
+            .put(format!(
+                "{}/teams/{}/members/{}",
+                &app.address, team.id, username
+            ))
+            .bearer_auth(token)
+            .json(&json!({ "role": role }))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+    }
+    team.id
+}
+
+async fn create_team_checklist(app: &common::TestApp, token: &str, team_id: Uuid) -> Checklist {
+    reqwest::Client::new()
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(token)
+        .json(&json!({
+            "team_id": team_id,
+            "title": "Store opening",
+            "tasks": [{"title": "Unlock doors"}]
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Checklist>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn create_team_makes_creator_its_owner() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    app.create_logged_in_user("krtek").await;
+
+    // Act
+    let team_id = create_team(&app, &token, &[("krtek", "viewer")]).await;
+    let team = client
+        .get(format!("{}/teams/{}", &app.address, team_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Team>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!("Operations", team.name);
+    assert_eq!(2, team.members.len());
+    assert_eq!("jozin", team.members[0].username);
+    assert_eq!(Role::Owner, team.members[0].role);
+    assert_eq!("krtek", team.members[1].username);
+    assert_eq!(Role::Viewer, team.members[1].role);
+}
+
+#[tokio::test]
+async fn team_members_can_read_team_checklists() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let viewer_token = app.create_logged_in_user("krtek").await;
+    let outsider_token = app.create_logged_in_user("mach").await;
+    let team_id = create_team(&app, &token, &[("krtek", "viewer")]).await;
+    let checklist = create_team_checklist(&app, &token, team_id).await;
+
+    // Act
+    let checklists = client
+        .get(format!("{}/checklists", &app.address))
+        .bearer_auth(&viewer_token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<ChecklistSummary>>()
+        .await
+        .unwrap();
+    let outsider_response = client
+        .get(format!("{}/checklists/{}", &app.address, checklist.id))
+        .bearer_auth(&outsider_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(Some(team_id), checklist.team_id);
+    assert_eq!(1, checklists.len());
+    assert_eq!(checklist.id, checklists[0].id);
+    assert_eq!(Some(team_id), checklists[0].team_id);
+    assert_eq!(404, outsider_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn viewers_cannot_edit_or_execute_team_checklists() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let viewer_token = app.create_logged_in_user("krtek").await;
+    let team_id = create_team(&app, &token, &[("krtek", "viewer")]).await;
+    let checklist = create_team_checklist(&app, &token, team_id).await;
+
+    // Act
+    let update_response = client
+        .put(format!("{}/checklists/{}", &app.address, checklist.id))
+        .bearer_auth(&viewer_token)
+        .json(&json!({"title": "Store closing", "tasks": []}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let execute_response = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address, checklist.id
+        ))
+        .bearer_auth(&viewer_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, update_response.status().as_u16());
+    assert_eq!(403, execute_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn executors_can_run_team_checklists() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let executor_token = app.create_logged_in_user("krtek").await;
+    let team_id = create_team(&app, &token, &[("krtek", "executor")]).await;
+    let checklist = create_team_checklist(&app, &token, team_id).await;
+
+    // Act
+    let execution_id = client
+        .post(format!(
+            "{}/checklists/{}/execute",
+            &app.address, checklist.id
+        ))
+        .bearer_auth(&executor_token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap()["execution_id"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    let task_response = client
+        .put(format!(
+            "{}/executions/{}/tasks/0/state",
+            &app.address, execution_id
+        ))
+        .bearer_auth(&executor_token)
+        .json(&json!({"state": "done"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let execution = client
+        .get(format!("{}/executions/{}", &app.address, execution_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, task_response.status().as_u16());
+    assert_eq!(json!(team_id), execution["team_id"]);
+    assert_eq!("done", execution["task_statuses"][0]["state"]);
+}
+
+#[tokio::test]
+async fn create_checklist_requires_editor_role_in_team() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let executor_token = app.create_logged_in_user("krtek").await;
+    let outsider_token = app.create_logged_in_user("mach").await;
+    let team_id = create_team(&app, &token, &[("krtek", "executor")]).await;
+    let body = json!({"team_id": team_id, "title": "Store opening", "tasks": []});
+
+    // Act
+    let executor_response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&executor_token)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let outsider_response = client
+        .post(format!("{}/checklists", &app.address))
+        .bearer_auth(&outsider_token)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, executor_response.status().as_u16());
+    assert_eq!(404, outsider_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn only_owners_manage_members_and_last_owner_stays() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let editor_token = app.create_logged_in_user("krtek").await;
+    app.create_logged_in_user("mach").await;
+    let team_id = create_team(&app, &token, &[("krtek", "editor")]).await;
+
+    // Act
+    let editor_response = client
+        .put(format!("{}/teams/{}/members/mach", &app.address, team_id))
+        .bearer_auth(&editor_token)
+        .json(&json!({"role": "viewer"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let demote_response = client
+        .put(format!("{}/teams/{}/members/jozin", &app.address, team_id))
+        .bearer_auth(&token)
+        .json(&json!({"role": "editor"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let remove_response = client
+        .delete(format!("{}/teams/{}/members/jozin", &app.address, team_id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, editor_response.status().as_u16());
+    assert_eq!(409, demote_response.status().as_u16());
+    assert_eq!(409, remove_response.status().as_u16());
+}
@@ -1,11 +1,13 @@
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 mod common;
 
-use secrecy::ExposeSecret;
 use serde::Deserialize;
 use serde_json::json;
 use std::{collections::HashMap, str::FromStr};
 use webapi::{
     models::{
+        session::Session,
         session_token::{SessionToken, SESSION_TOKEN_LENGTH},
         user::UserProfile,
     },
@@ -15,6 +17,7 @@ use webapi::{
 #[derive(Deserialize)]
 struct LoginUserResponse {
     token: String,
+    refresh_token: String,
 }
 
 #[tokio::test]
@@ -188,7 +191,7 @@ async fn creating_user_returns_a_409_when_user_exists() {
     let sesion_data = sqlx::query!(
         r#"SELECT user_id FROM sessions
         WHERE token = $1"#,
-        token.expose_secret()
+        token
     )
     .fetch_one(&app.db_pool)
     .await
@@ -246,3 +249,446 @@ async fn getting_current_response_with_invalid_authorization_header_returns_401(
 
     assert_eq!(401, response.status().as_u16());
 }
+
+async fn log_in(app: &common::TestApp, username: &str) -> String {
+    log_in_with_refresh_token(app, username).await.token
+}
+
+async fn log_in_with_refresh_token(app: &common::TestApp, username: &str) -> LoginUserResponse {
+    reqwest::Client::new()
+        .post(format!("{}/user/login", &app.address))
+        .header("User-Agent", "checkmate-tests")
+        .json(&json!({"username": username, "password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<LoginUserResponse>()
+        .await
+        .unwrap()
+}
+
+async fn refresh(app: &common::TestApp, refresh_token: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/user/refresh", &app.address))
+        .json(&json!({ "refresh_token": refresh_token }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn logged_out_token_is_rejected() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let logout_response = client
+        .post(format!("{}/user/logout", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(200, logout_response.status().as_u16());
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn sessions_are_listed_with_the_current_one_marked() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    app.create_logged_in_user("jozin").await;
+    let token = log_in(&app, "jozin").await;
+
+    // Act
+    let sessions = client
+        .get(format!("{}/user/sessions", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<Session>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(2, sessions.len());
+    assert!(!sessions[0].current);
+    assert!(sessions[1].current);
+    assert_eq!(Some("checkmate-tests"), sessions[1].user_agent.as_deref());
+    assert!(sessions[1].last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn logging_out_everywhere_else_keeps_the_current_session() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let other_token = app.create_logged_in_user("jozin").await;
+    let token = log_in(&app, "jozin").await;
+
+    // Act
+    let response = client
+        .delete(format!("{}/user/sessions", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let current_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let other_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+    assert_eq!(200, current_response.status().as_u16());
+    assert_eq!(401, other_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn deleting_session_of_another_user_returns_404() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    let other_token = app.create_logged_in_user("krtek").await;
+    let other_session = client
+        .get(format!("{}/user/sessions", &app.address))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<Session>>()
+        .await
+        .unwrap()
+        .remove(0);
+
+    // Act
+    let response = client
+        .delete(format!(
+            "{}/user/sessions/{}",
+            &app.address, other_session.id
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn using_session_extends_it() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    sqlx::query!("UPDATE sessions SET valid_until = now() + interval '1 minute'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let session = sqlx::query!("SELECT valid_until FROM sessions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(session.valid_until > chrono::Utc::now() + chrono::Duration::hours(1));
+}
+
+#[tokio::test]
+async fn expired_session_can_be_refreshed_once() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    app.create_logged_in_user("jozin").await;
+    sqlx::query!("DELETE FROM sessions")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let login = log_in_with_refresh_token(&app, "jozin").await;
+    sqlx::query!("UPDATE sessions SET valid_until = now() - interval '1 minute'")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let expired_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&login.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let refreshed = refresh(&app, &login.refresh_token)
+        .await
+        .json::<LoginUserResponse>()
+        .await
+        .unwrap();
+    let refreshed_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&refreshed.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let reused_response = refresh(&app, &login.refresh_token).await;
+
+    // Assert
+    assert_eq!(401, expired_response.status().as_u16());
+    assert_eq!(200, refreshed_response.status().as_u16());
+    assert_eq!(401, reused_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn refreshed_session_expires_with_its_refresh_token() {
+    // Arrange
+    let app = common::spawn_app().await;
+    app.create_logged_in_user("jozin").await;
+    sqlx::query!("DELETE FROM sessions")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let login = log_in_with_refresh_token(&app, "jozin").await;
+    sqlx::query!(
+        "UPDATE sessions SET refresh_valid_until = now() + interval '1 hour', valid_until = now() - interval '1 minute'"
+    )
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let refreshed = refresh(&app, &login.refresh_token)
+        .await
+        .json::<LoginUserResponse>()
+        .await
+        .unwrap();
+    let refreshed_again = refresh(&app, &refreshed.refresh_token).await;
+    let session = sqlx::query!("SELECT valid_until, expires_at, refresh_valid_until FROM sessions")
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, refreshed_again.status().as_u16());
+    assert_eq!(session.refresh_valid_until, Some(session.expires_at));
+    assert_eq!(session.refresh_valid_until, Some(session.valid_until));
+}
+
+#[tokio::test]
+async fn changing_password_logs_out_other_sessions() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let other_token = app.create_logged_in_user("jozin").await;
+    let token = log_in(&app, "jozin").await;
+
+    // Act
+    let response = client
+        .put(format!("{}/user/password", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"current_password": "123", "new_password": "456"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let current_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let other_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let new_login_response = client
+        .post(format!("{}/user/login", &app.address))
+        .json(&json!({"username": "jozin", "password": "456"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(204, response.status().as_u16());
+    assert_eq!(200, current_response.status().as_u16());
+    assert_eq!(401, other_response.status().as_u16());
+    assert_eq!(200, new_login_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn changing_password_with_wrong_current_password_returns_403() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let response = client
+        .put(format!("{}/user/password", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"current_password": "wrong", "new_password": "456"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn password_can_be_reset_once_with_notified_token() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let old_token = app.create_logged_in_user("jozin").await;
+
+    // Act
+    let request_response = client
+        .post(format!("{}/user/password-reset", &app.address))
+        .json(&json!({"username": "jozin"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let unknown_response = client
+        .post(format!("{}/user/password-reset", &app.address))
+        .json(&json!({"username": "nobody"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let notifications = app.notifications();
+    let reset_token = notifications[0]["token"].as_str().unwrap();
+    let reset = json!({"token": reset_token, "new_password": "456"});
+    let reset_response = client
+        .post(format!("{}/user/password-reset/confirm", &app.address))
+        .json(&reset)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let reused_response = client
+        .post(format!("{}/user/password-reset/confirm", &app.address))
+        .json(&reset)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let old_session_response = client
+        .get(format!("{}/user", &app.address))
+        .bearer_auth(&old_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let new_login_response = client
+        .post(format!("{}/user/login", &app.address))
+        .json(&json!({"username": "jozin", "password": "456"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(202, request_response.status().as_u16());
+    assert_eq!(202, unknown_response.status().as_u16());
+    assert_eq!(1, notifications.len());
+    assert_eq!("jozin", notifications[0]["username"]);
+    assert_eq!("password_reset", notifications[0]["kind"]);
+    assert_eq!(204, reset_response.status().as_u16());
+    assert_eq!(401, reused_response.status().as_u16());
+    assert_eq!(401, old_session_response.status().as_u16());
+    assert_eq!(200, new_login_response.status().as_u16());
+}
+
+#[tokio::test]
+async fn changing_password_revokes_outstanding_reset_tokens() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    let token = app.create_logged_in_user("jozin").await;
+    client
+        .post(format!("{}/user/password-reset", &app.address))
+        .json(&json!({"username": "jozin"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let reset_token = app.notifications()[0]["token"].as_str().unwrap().to_owned();
+
+    // Act
+    client
+        .put(format!("{}/user/password", &app.address))
+        .bearer_auth(&token)
+        .json(&json!({"current_password": "123", "new_password": "456"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let response = client
+        .post(format!("{}/user/password-reset/confirm", &app.address))
+        .json(&json!({"token": reset_token, "new_password": "789"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn session_ip_ignores_forwarded_headers_by_default() {
+    // Arrange
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::new();
+    app.create_logged_in_user("jozin").await;
+    sqlx::query!("DELETE FROM sessions")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let token = client
+        .post(format!("{}/user/login", &app.address))
+        .header("X-Forwarded-For", "203.0.113.7")
+        .json(&json!({"username": "jozin", "password": "123"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<LoginUserResponse>()
+        .await
+        .unwrap()
+        .token;
+    let sessions = client
+        .get(format!("{}/user/sessions", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<Session>>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(Some("127.0.0.1"), sessions[0].ip.as_deref());
+}